@@ -3,9 +3,10 @@
 use crate::utils::config::load_or_init_config;
 use mongodb::{options::ClientOptions, Client, Database};
 use std::error::Error;
+use tracing::info;
 
 pub async fn create_client() -> Result<Database, Box<dyn Error>> {
-    println!("Connecting to MongoDB");
+    info!("Connecting to MongoDB");
     let config = load_or_init_config().await?;
     let mut client_options = ClientOptions::parse(config.server).await?;
     let name = config.database;
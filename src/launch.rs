@@ -1,8 +1,25 @@
-use crate::utils::{
-    aes::generate_aes256_key,
-    rsa::{generate_keypair, save_keypair},
+use crate::{
+    routers::exports::schedules::run_due_schedules,
+    utils::{
+        aes::generate_aes256_key,
+        config::Config,
+        exports::tasks::cleanup_expired_tasks,
+        rsa::{generate_keypair, save_keypair},
+        storage::signature::SIGNING_KEY_FILE,
+    },
 };
-use tokio::fs::{try_exists, write};
+use bson::{doc, Bson, Document};
+use mongodb::{options::IndexOptions, Database, IndexModel};
+use socketioxide::SocketIo;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    fs::{try_exists, write},
+    sync::Mutex,
+};
+use tracing::{info, warn};
 
 pub async fn generate_rsa_keypair() {
     let private_exists = try_exists("private.pem").await.unwrap();
@@ -20,3 +37,159 @@ pub async fn generate_aes_key() {
         write("aes.key", key).await.unwrap();
     }
 }
+
+pub async fn generate_signing_key() {
+    let exists = try_exists(SIGNING_KEY_FILE).await.unwrap();
+    if !exists {
+        let key = generate_aes256_key();
+        write(SIGNING_KEY_FILE, key).await.unwrap();
+    }
+}
+
+fn normalize_ids(field: &str) -> Bson {
+    // Strings that are not valid ObjectIds are kept as they are
+    let input = format!("${}", field);
+    doc! {"$map": {
+        "input": {"$ifNull": [input, []]},
+        "as": "member",
+        "in": {"$mergeObjects": [
+            "$$member",
+            {"_id": {"$convert": {
+                "input": "$$member._id",
+                "to": "objectId",
+                "onError": "$$member._id",
+            }}},
+        ]},
+    }}
+    .into()
+}
+
+/// Converts activity members stored with hex string IDs to ObjectIds. Only
+/// activities that still have such members are touched, so it is safe to run
+/// on every start.
+pub async fn normalize_member_ids(db: &Database) -> Result<u64, String> {
+    let collection = db.collection::<Document>("activities");
+    let result = collection
+        .update_many(
+            doc! {"$or": [
+                {"members._id": {"$type": "string"}},
+                {"removedMembers._id": {"$type": "string"}},
+            ]},
+            vec![doc! {"$set": {
+                "members": normalize_ids("members"),
+                "removedMembers": normalize_ids("removedMembers"),
+            }}],
+            None,
+        )
+        .await;
+    if let Err(e) = result {
+        return Err(format!("Failed to normalize member IDs: {}", e));
+    }
+    Ok(result.unwrap().modified_count)
+}
+
+fn unquote_statuses(field: &str) -> Bson {
+    let input = format!("${}", field);
+    doc! {"$map": {
+        "input": {"$ifNull": [input, []]},
+        "as": "member",
+        "in": {"$mergeObjects": [
+            "$$member",
+            {"status": {"$cond": [
+                {"$eq": [{"$type": "$$member.status"}, "string"]},
+                {"$trim": {"input": "$$member.status", "chars": "\""}},
+                "$$member.status",
+            ]}},
+        ]},
+    }}
+    .into()
+}
+
+/// Older versions stored member statuses as JSON strings, e.g. `"pending"`
+/// with the quotes, which no longer deserialize. Only activities that still
+/// have such statuses are touched, so it is safe to run on every start.
+pub async fn unquote_member_statuses(db: &Database) -> Result<u64, String> {
+    let collection = db.collection::<Document>("activities");
+    let result = collection
+        .update_many(
+            doc! {"$or": [
+                {"members.status": {"$regex": "^\""}},
+                {"removedMembers.status": {"$regex": "^\""}},
+            ]},
+            vec![doc! {"$set": {
+                "members": unquote_statuses("members"),
+                "removedMembers": unquote_statuses("removedMembers"),
+            }}],
+            None,
+        )
+        .await;
+    if let Err(e) = result {
+        return Err(format!("Failed to unquote member statuses: {}", e));
+    }
+    Ok(result.unwrap().modified_count)
+}
+
+/// Certificates are verified by their code, so no two may share one. Creating
+/// an index that already exists does nothing.
+pub async fn create_certificate_index(db: &Database) -> Result<(), String> {
+    let collection = db.collection::<Document>("certificates");
+    let index = IndexModel::builder()
+        .keys(doc! {"code": 1})
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = collection.create_index(index, None).await {
+        return Err(format!("Failed to index certificate codes: {}", e));
+    }
+    Ok(())
+}
+
+/// Certificates embed this font, so a missing one is reported at startup
+/// instead of by every certificate request.
+pub async fn check_certificate_font(config: &Config) -> Result<(), String> {
+    let font = config.certificates.clone().unwrap_or_default().font;
+    match try_exists(&font).await {
+        Ok(true) => Ok(()),
+        _ => Err(format!(
+            "Certificate font {} not found, see the README to install it",
+            font
+        )),
+    }
+}
+
+/// Deletes expired export tasks and their files once an hour.
+pub fn spawn_export_cleanup(db: Database, retention: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+            match cleanup_expired_tasks(&db, retention, now).await {
+                Ok(0) => {}
+                Ok(deleted) => info!("Deleted {} expired export tasks", deleted),
+                Err(e) => warn!("{}", e),
+            }
+        }
+    });
+}
+
+/// Starts the exports of due schedules once a minute.
+pub fn spawn_export_scheduler(db: Arc<Mutex<Database>>, config: Arc<Config>, io: SocketIo) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            match run_due_schedules(db.clone(), config.clone(), io.clone(), now).await {
+                Ok(0) => {}
+                Ok(started) => info!("Started {} scheduled exports", started),
+                Err(e) => warn!("{}", e),
+            }
+        }
+    });
+}
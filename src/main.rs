@@ -2,26 +2,38 @@ extern crate chrono;
 mod calc;
 mod database;
 mod launch;
+// Models mirror the database schema, not every type is used by a handler yet.
+#[allow(dead_code)]
 mod models;
-// mod routers;
+mod routers;
 mod tests;
 mod utils;
-use crate::models::exports::ExportState;
 use axum::{
+    extract::DefaultBodyLimit,
     http::Method,
+    routing::{delete, get, post, put},
     Extension, Router,
 };
-use launch::{generate_aes_key, generate_rsa_keypair};
+use launch::{
+    check_certificate_font, create_certificate_index, generate_aes_key, generate_rsa_keypair,
+    generate_signing_key, normalize_member_ids, spawn_export_cleanup, spawn_export_scheduler,
+    unquote_member_statuses,
+};
+use mongodb::Database;
 use serde_json::Value;
 use socketioxide::{
     extract::{AckSender, Bin, Data, SocketRef},
     SocketIo,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
+use tracing::info;
+use utils::exports::tasks::fail_interrupted_tasks;
+
+fn on_connect(socket: SocketRef, Data(data): Data<Value>, db: Arc<Mutex<Database>>) {
+    routers::exports::subscribe_export(&socket, &data, db);
 
-fn on_connect(socket: SocketRef, Data(data): Data<Value>) {
     socket.emit("auth", data).ok();
 
     socket.on(
@@ -47,13 +59,64 @@ async fn main() {
         .await
         .expect("Failed to create client");
 
-    let shared_export_state = Arc::new(Mutex::new(HashMap::new()) as ExportState);
+    let migrated = normalize_member_ids(&client)
+        .await
+        .expect("Failed to migrate member IDs");
+    if migrated > 0 {
+        info!("Normalized member IDs in {} activities", migrated);
+    }
+
+    let unquoted = unquote_member_statuses(&client)
+        .await
+        .expect("Failed to migrate member statuses");
+    if unquoted > 0 {
+        info!("Unquoted member statuses in {} activities", unquoted);
+    }
+
+    create_certificate_index(&client)
+        .await
+        .expect("Failed to index certificate codes");
+
+    let interrupted = fail_interrupted_tasks(&client)
+        .await
+        .expect("Failed to update interrupted export tasks");
+    if interrupted > 0 {
+        info!("Marked {} interrupted export tasks as failed", interrupted);
+    }
+
+    let cleanup_client = client.clone();
 
     let shared_client = Arc::new(Mutex::new(client));
 
-    let (_, io) = SocketIo::new_layer();
+    let config = utils::config::load_or_init_config()
+        .await
+        .expect("Failed to load config");
 
-    io.ns("/", on_connect);
+    check_certificate_font(&config)
+        .await
+        .expect("Failed to check certificate font");
+
+    let shared_storage = utils::storage::create_storage(&config);
+
+    // Room for a member's whole image quota plus multipart overhead
+    let image_body_limit =
+        config.images.clone().unwrap_or_default().max_member_size as usize + 1024 * 1024;
+
+    spawn_export_cleanup(
+        cleanup_client,
+        config.exports.clone().unwrap_or_default().retention,
+    );
+
+    let shared_config = Arc::new(config);
+
+    let (socket_layer, io) = SocketIo::new_layer();
+
+    let socket_client = shared_client.clone();
+    io.ns("/", move |socket: SocketRef, data: Data<Value>| {
+        on_connect(socket, data, socket_client.clone())
+    });
+
+    spawn_export_scheduler(shared_client.clone(), shared_config.clone(), io.clone());
 
     // Generate RSA keypair
     generate_rsa_keypair().await;
@@ -61,10 +124,122 @@ async fn main() {
     // Generate AES key
     generate_aes_key().await;
 
+    // Generate the key signing object URLs
+    generate_signing_key().await;
+
     // Set up the router
     let app = Router::new()
+        .route("/auth/login", post(routers::auth::login))
+        .route(
+            "/activity",
+            get(routers::activities::read::read_all)
+                .post(routers::activities::insert::insert_activity),
+        )
+        .route(
+            "/activity/:id",
+            get(routers::activities::read::read_one)
+                .delete(routers::activities::remove::remove_activity),
+        )
+        .route(
+            "/activity/:id/name",
+            put(routers::activities::update::update_activity_name),
+        )
+        .route(
+            "/activity/:id/description",
+            put(routers::activities::update::update_activity_description),
+        )
+        .route(
+            "/activity/:id/member",
+            post(routers::activities::members::insert::insert_member_into_activity),
+        )
+        .route(
+            "/activity/:id/members",
+            post(routers::activities::members::insert::insert_members_into_activity),
+        )
+        .route(
+            "/activity/:id/member/:member_id",
+            get(routers::activities::members::read::read_member)
+                .delete(routers::activities::members::remove::remove_member_from_activity),
+        )
+        .route(
+            "/activity/:id/member/:member_id/history",
+            get(routers::activities::members::history::read_member_history),
+        )
+        .route(
+            "/activity/:id/member/:member_id/images",
+            get(routers::activities::members::images::read_member_images)
+                .post(routers::activities::members::images::upload_member_images)
+                .layer(DefaultBodyLimit::max(image_body_limit)),
+        )
+        .route(
+            "/activity/:id/member/:member_id/status",
+            put(routers::activities::members::update::update_member_status),
+        )
+        .route(
+            "/activity/:id/member/:member_id/mode",
+            put(routers::activities::members::update::update_member_mode),
+        )
+        .route(
+            "/activity/:id/member/:member_id/impression",
+            put(routers::activities::members::update::update_member_impression),
+        )
+        .route(
+            "/group/:id/progress",
+            get(routers::groups::read_class_progress),
+        )
+        .route(
+            "/review",
+            get(routers::reviews::read_review_queue).post(routers::reviews::review_members),
+        )
+        .route(
+            "/user/:id/activity",
+            get(routers::users::activity::read_user_activities),
+        )
+        .route(
+            "/user/:id/progress",
+            get(routers::users::progress::read_user_progress),
+        )
+        .route(
+            "/user/:id/certificate",
+            get(routers::users::certificate::issue_certificate),
+        )
+        .route(
+            "/user/:id/time",
+            get(routers::users::time::calculate_user_activity_time),
+        )
+        .route(
+            "/export/activity-times",
+            post(routers::exports::export_activity_times),
+        )
+        .route("/export/roster", post(routers::exports::export_roster))
+        .route(
+            "/export/class",
+            post(routers::exports::export_class_activity_times),
+        )
+        .route(
+            "/export/schedules",
+            get(routers::exports::schedules::read_export_schedules)
+                .post(routers::exports::schedules::create_export_schedule),
+        )
+        .route(
+            "/export/schedules/:id",
+            delete(routers::exports::schedules::remove_export_schedule),
+        )
+        .route("/export/:id", get(routers::exports::query_export_status))
+        .route(
+            "/export/:id/download",
+            get(routers::exports::download_export),
+        )
+        .route("/storage/*key", get(routers::storage::read_object))
+        .route(
+            "/certificate/:code",
+            get(routers::certificates::verify_certificate),
+        )
         .layer(Extension(shared_client.clone()))
-        .layer(Extension(shared_export_state.clone()))
+        .layer(Extension(shared_storage))
+        .layer(Extension(shared_config))
+        .layer(Extension(io))
+        .layer(socket_layer)
         .layer(
             CorsLayer::new()
                 .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
@@ -1,4 +1,4 @@
-use crate::models::utils::datetime_or_u64;
+use crate::models::{attendances::ActivityMember, utils::datetime_or_u64};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
@@ -45,7 +45,8 @@ pub struct Activity {
     pub updated_at: u64,
     pub creator: ObjectId,
     pub status: ActivityStatus,
-    pub members: Vec<ObjectId>,
+    pub members: Option<Vec<ActivityMember>>,
+    pub removed_members: Option<Vec<ActivityMember>>,
     pub location: Option<String>,
     pub category: Option<SpecialActivityCategory>,
 }
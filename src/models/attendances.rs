@@ -20,6 +20,16 @@ pub enum AttendanceMode {
     SocialPractice,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttendanceAction {
+    Status,
+    Duration,
+    Impression,
+    Mode,
+    Remove,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct AttendanceHistory {
     pub impression: String,
@@ -31,6 +41,12 @@ pub struct AttendanceHistory {
     )]
     pub actor: ObjectId, // ObjectId
     pub result: AttendanceStatus,
+    pub mode: Option<AttendanceMode>,
+    pub action: Option<AttendanceAction>,
+    /// Why an auditor approved, refused or rejected the member.
+    pub reason: Option<String>,
+    /// Set when an admin credited a duration outside the configured rules.
+    pub overridden: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -42,4 +58,7 @@ pub struct ActivityMember {
     pub mode: AttendanceMode,
     pub history: Option<Vec<AttendanceHistory>>,
     pub images: Option<Vec<String>>,
+    /// Total size in bytes of the stored original images.
+    #[serde(rename = "imageSize")]
+    pub image_size: Option<u64>,
 }
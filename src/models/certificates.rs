@@ -0,0 +1,49 @@
+use crate::routers::users::time::UserActivityTime;
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// A certificate of volunteer hours as issued, kept so its verification
+/// code can be checked later.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Certificate {
+    pub _id: ObjectId,
+    pub code: String,
+    pub user: ObjectId,
+    pub name: String,
+    #[serde(rename = "issuedAt")]
+    pub issued_at: u64, // Unix timestamp
+    #[serde(rename = "issuedBy")]
+    pub issued_by: ObjectId,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub time: UserActivityTime,
+    /// SHA-256 of the issued PDF, in hex.
+    pub hash: String,
+}
+
+/// What anyone holding a certificate's code may learn about it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CertificateVerification {
+    pub code: String,
+    pub name: String,
+    #[serde(rename = "issuedAt")]
+    pub issued_at: u64,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub time: UserActivityTime,
+    pub hash: String,
+}
+
+impl From<Certificate> for CertificateVerification {
+    fn from(certificate: Certificate) -> Self {
+        CertificateVerification {
+            code: certificate.code,
+            name: certificate.name,
+            issued_at: certificate.issued_at,
+            from: certificate.from,
+            to: certificate.to,
+            time: certificate.time,
+            hash: certificate.hash,
+        }
+    }
+}
@@ -1,24 +1,84 @@
 use bson::{doc, oid::ObjectId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::sync::Mutex;
-use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum ExportFormat {
-    CSV,
-    JSON,
+    // Also accept the names the variants serialized to when they were
+    // spelled `CSV` and `JSON`
+    #[serde(alias = "c-s-v")]
+    Csv,
+    #[serde(alias = "j-s-o-n")]
+    Json,
     Excel,
 }
 
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Excel => "xlsx",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Excel => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ExportActivityTimesOptions {
-    pub start: u64, // Unix timestamp
-    pub end: u64,   // Unix timestamp
+    pub start: u64, // Unix timestamp, 0 for no lower bound
+    pub end: u64,   // Unix timestamp, 0 for no upper bound
     pub format: ExportFormat,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExportRosterOptions {
+    /// Activity IDs, or none for every activity between `start` and `end`.
+    pub activities: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub options: ExportActivityTimesOptions,
+}
+
+/// What a task exports.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ExportKind {
+    #[default]
+    ActivityTimes,
+    /// Members of the given activities, or of every activity in the range
+    /// when empty.
+    Roster { activities: Vec<ObjectId> },
+    /// Activity times of the students in `classes`, with a total per class.
+    Class { classes: Vec<ObjectId> },
+}
+
+impl ExportKind {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportKind::ActivityTimes => "activity-times",
+            ExportKind::Roster { .. } => "roster",
+            ExportKind::Class { .. } => "class-activity-times",
+        }
+    }
+
+    /// Column whose values split an Excel export into sheets.
+    pub fn sheet_column(&self) -> &'static str {
+        match self {
+            ExportKind::ActivityTimes | ExportKind::Class { .. } => "class",
+            ExportKind::Roster { .. } => "activity",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum TaskStatus {
@@ -30,12 +90,18 @@ pub enum TaskStatus {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Task {
-    pub time: u64, // Unix timestamp
+    pub _id: String, // UUID
+    pub time: u64,   // Unix timestamp in milliseconds
     pub actioner: ObjectId,
+    #[serde(default)]
+    pub kind: ExportKind,
     pub options: ExportActivityTimesOptions,
     pub status: TaskStatus,
-    pub result: Option<String>,
+    pub result: Option<String>, // Download URL
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
     pub percent: Option<f64>,
+    /// The schedule that started the task, if any.
+    #[serde(default)]
+    pub schedule: Option<ObjectId>,
 }
-
-pub type ExportState = Mutex<HashMap<Uuid, Task>>;
@@ -1,9 +1,11 @@
 pub mod activities;
 pub mod attendances;
+pub mod certificates;
 pub mod exports;
 pub mod groups;
 pub mod notifications;
 pub mod response;
+pub mod schedules;
 pub mod users;
-mod utils;
+pub mod utils;
 pub mod volunteers;
@@ -35,6 +35,12 @@ pub struct MetadataSize {
     pub size: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct MetadataPage {
+    pub size: u64,
+    pub next_cursor: Option<String>,
+}
+
 pub fn create_error(code: StatusCode, message: String) -> (StatusCode, Json<String>) {
     let resposne = ErrorResponse {
         status: ResponseStatus::Error,
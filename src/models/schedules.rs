@@ -0,0 +1,49 @@
+use crate::models::exports::{ExportActivityTimesOptions, ExportFormat, ExportKind};
+use bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// An export started again and again on a cron schedule. Each run is an
+/// ordinary export task; only the last `keep` of them are kept.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportSchedule {
+    pub _id: ObjectId,
+    pub owner: ObjectId,
+    /// `minute hour day month weekday` in the configured timezone.
+    pub cron: String,
+    pub kind: ExportKind,
+    pub format: ExportFormat,
+    /// Days before each run to export, or every record when not set.
+    pub days: Option<u64>,
+    pub keep: u64,
+    #[serde(rename = "nextRun")]
+    pub next_run: u64, // Unix timestamp
+    #[serde(rename = "lastRun")]
+    pub last_run: Option<u64>, // Unix timestamp
+}
+
+impl ExportSchedule {
+    /// Options of the run started at `now`, a UNIX timestamp.
+    pub fn options(&self, now: u64) -> ExportActivityTimesOptions {
+        ExportActivityTimesOptions {
+            start: self
+                .days
+                .map(|days| now.saturating_sub(days * 86400))
+                .unwrap_or(0),
+            end: now,
+            format: self.format.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExportScheduleOptions {
+    pub cron: String,
+    /// `activity-times`, `class` or `roster`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Activity IDs for rosters, or none for every activity in the range.
+    pub activities: Option<Vec<String>>,
+    pub format: ExportFormat,
+    pub days: Option<u64>,
+    pub keep: Option<u64>,
+}
@@ -39,11 +39,7 @@ pub trait UserTrait {
 impl UserTrait for User {
     async fn valid_password(self, password: String) -> bool {
         let result = verify(password, self.password.as_str());
-        if let Ok(valid) = result {
-            valid
-        } else {
-            false
-        }
+        result.unwrap_or_default()
     }
     async fn set_password(&mut self, password: String) -> () {
         let result = hash(password, 12);
@@ -64,7 +60,7 @@ impl UserTrait for User {
                 let mut permissions: Vec<GroupPermission> = vec![];
                 for group in groups {
                     let id = ObjectId::from_str(group.to_hex().as_str());
-                    if let Err(_) = id {
+                    if id.is_err() {
                         return Err("Invalid group".to_string());
                     }
                     let id: ObjectId = id.unwrap();
@@ -57,26 +57,25 @@ pub async fn insert_activity(
     }
     let activity = bson::to_document(&activity);
     if let Ok(activity) = activity {
-        if let Ok(_) = collection.insert_one(activity, None).await {
+        if collection.insert_one(activity, None).await.is_ok() {
             let response: SuccessResponse<_, ()> = SuccessResponse {
                 status: ResponseStatus::Success,
                 code: 200,
                 data: (),
                 metadata: None,
-            }
-            .into();
+            };
             let response = serde_json::to_string(&response).unwrap();
             (StatusCode::OK, Json(response))
         } else {
-            return create_error(
+            create_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Failed to insert activity".to_string(),
-            );
+            )
         }
     } else {
-        return create_error(
+        create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to insert activity".to_string(),
-        );
+        )
     }
 }
@@ -1 +1,128 @@
+use crate::{
+    models::{
+        activities::Activity,
+        attendances::{ActivityMember, AttendanceAction, AttendanceHistory},
+        groups::GroupPermission,
+        response::{create_error, ResponseStatus, SuccessResponse},
+    },
+    utils::{groups::classes::share_class, jwt::UserData},
+};
+use axum::{
+    extract::{Extension, Json, Path},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use bson::{doc, oid::ObjectId, Document};
+use chrono::Utc;
+use mongodb::{Collection, Database};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
 
+/// Snapshots the member's current state as a history entry made by `actor`.
+pub fn create_history(
+    member: &ActivityMember,
+    actor: ObjectId,
+    action: AttendanceAction,
+) -> AttendanceHistory {
+    AttendanceHistory {
+        impression: member.impression.clone().unwrap_or_default(),
+        duration: member.duration,
+        time: Utc::now().to_rfc3339(),
+        actor,
+        result: member.status.clone(),
+        mode: Some(member.mode.clone()),
+        action: Some(action),
+        reason: None,
+        overridden: None,
+    }
+}
+
+/// Builds the update that applies `set` to the member matched by the
+/// positional operator and appends `entry` to its history. The entry is
+/// pushed rather than written back with the history that was read, so
+/// concurrent updates of the same member keep each other's entries.
+pub fn record_history(
+    mut set: Document,
+    member: &ActivityMember,
+    entry: &AttendanceHistory,
+) -> Result<Document, bson::ser::Error> {
+    let entry = bson::to_bson(entry)?;
+    if member.history.is_none() {
+        // $push fails on a null field, so older members get a new array
+        set.insert("members.$.history", vec![entry]);
+        return Ok(doc! {"$set": set});
+    }
+    Ok(doc! {"$set": set, "$push": {"members.$.history": entry}})
+}
+
+pub async fn read_member_history(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Path((id, member_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
+    }
+    let member_id = member_id.unwrap();
+    if user.perms.contains(&GroupPermission::Admin)
+        || user.perms.contains(&GroupPermission::Auditor)
+        || user.id == member_id.to_hex()
+    {
+    } else if user.perms.contains(&GroupPermission::Secretary) {
+        let user_id = ObjectId::from_str(&user.id);
+        if user_id.is_err() {
+            return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+        }
+        let same = share_class(&db, user_id.unwrap(), member_id).await;
+        if let Err(e) = same {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to validate user: {}", e),
+            );
+        }
+        if !same.unwrap() {
+            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        }
+    } else {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let activity = collection
+        .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+        .await;
+    if activity.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        );
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
+    }
+    let member = activity
+        .unwrap()
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
+    }
+    let history = member.unwrap().history.unwrap_or_default();
+    let response: SuccessResponse<Vec<AttendanceHistory>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: history,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
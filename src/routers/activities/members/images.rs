@@ -0,0 +1,428 @@
+use crate::{
+    models::{
+        activities::Activity,
+        attendances::{ActivityMember, AttendanceStatus},
+        groups::GroupPermission,
+        response::{create_error, ResponseStatus, SuccessResponse},
+    },
+    routers::activities::members::read::can_read_member,
+    utils::{
+        config::{Config, ImageConfig},
+        images::{
+            detect_mime, image_extension, rendition_keys, strip_metadata,
+            thumbnail::create_renditions, ALLOWED_IMAGE_TYPES,
+        },
+        jwt::UserData,
+        storage::{
+            signature::{read_signing_key, sign_object_key, SIGNED_URL_TTL},
+            Storage,
+        },
+    },
+};
+use axum::{
+    extract::{Extension, Json, Path},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_extra::extract::Multipart;
+use bson::{doc, oid::ObjectId};
+use chrono::Utc;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SignedImage {
+    pub key: String,
+    pub url: String,
+    pub thumbnail: String,
+    pub preview: String,
+    pub expires: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct UploadedImage {
+    pub key: String,
+    pub thumbnail: String,
+    pub preview: String,
+}
+
+fn signed_url(secret: &str, key: &str, expires: i64) -> String {
+    let signature = sign_object_key(secret.as_bytes(), key, expires);
+    format!(
+        "/storage/{}?expires={}&signature={}",
+        key, expires, signature
+    )
+}
+
+/// Signs the URLs of the images and their renditions for a short while.
+pub fn sign_images(keys: Vec<String>) -> Vec<SignedImage> {
+    let secret = read_signing_key();
+    let expires = Utc::now().timestamp() + SIGNED_URL_TTL;
+    keys.into_iter()
+        .map(|key| {
+            let (thumbnail, preview) = rendition_keys(&key);
+            SignedImage {
+                url: signed_url(&secret, &key, expires),
+                thumbnail: signed_url(&secret, &thumbnail, expires),
+                preview: signed_url(&secret, &preview, expires),
+                key,
+                expires,
+            }
+        })
+        .collect()
+}
+
+pub async fn read_member_images(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Path((id, member_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
+    }
+    let member_id = member_id.unwrap();
+    let allowed = can_read_member(&db, &user, member_id).await;
+    if let Err(e) = allowed {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to validate user: {}", e),
+        );
+    }
+    if !allowed.unwrap() {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let activity = collection
+        .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+        .await;
+    if activity.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        );
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
+    }
+    let member = activity
+        .unwrap()
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
+    }
+    let images = sign_images(member.unwrap().images.unwrap_or_default());
+    let response: SuccessResponse<Vec<SignedImage>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: images,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
+
+/// Checks that `count` more images of `size` bytes fit in the member's quota.
+fn check_quota(
+    config: &ImageConfig,
+    member: &ActivityMember,
+    count: usize,
+    size: u64,
+) -> Result<(), String> {
+    let images = member.images.as_ref().map_or(0, |images| images.len());
+    if images + count > config.max_member_images {
+        return Err(format!(
+            "A member may attach at most {} images",
+            config.max_member_images
+        ));
+    }
+    if member.image_size.unwrap_or(0) + size > config.max_member_size {
+        return Err(format!(
+            "A member may attach at most {} bytes of images",
+            config.max_member_size
+        ));
+    }
+    Ok(())
+}
+
+pub async fn upload_member_images(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(storage): Extension<Arc<dyn Storage>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path((id, member_id)): Path<(String, String)>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let image_config = config.images.clone().unwrap_or_default();
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
+    }
+    let member_id = member_id.unwrap();
+    let user_id = ObjectId::from_str(user.id.as_str());
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let member = find_member(&db, activity_id, member_id).await;
+    if let Err((code, e)) = member {
+        return create_error(code, e);
+    }
+    let member = member.unwrap();
+    if user.perms.contains(&GroupPermission::Admin)
+        || (user_id == member_id
+            && (member.status == AttendanceStatus::Draft
+                || member.status == AttendanceStatus::Rejected))
+    {
+    } else {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let mut files = vec![];
+    loop {
+        let field = multipart.next_field().await;
+        if field.is_err() {
+            return create_error(
+                StatusCode::BAD_REQUEST,
+                "Invalid multipart body".to_string(),
+            );
+        }
+        let field = field.unwrap();
+        if field.is_none() {
+            break;
+        }
+        let data = field.unwrap().bytes().await;
+        if data.is_err() {
+            return create_error(StatusCode::BAD_REQUEST, "Failed to read image".to_string());
+        }
+        let data = data.unwrap();
+        if data.len() as u64 > image_config.max_file_size {
+            return create_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "An image may be at most {} bytes",
+                    image_config.max_file_size
+                ),
+            );
+        }
+        files.push(data);
+    }
+    if files.is_empty() {
+        return create_error(StatusCode::BAD_REQUEST, "No image uploaded".to_string());
+    }
+    let size = files.iter().map(|data| data.len() as u64).sum();
+    if let Err(e) = check_quota(&image_config, &member, files.len(), size) {
+        return create_error(StatusCode::PAYLOAD_TOO_LARGE, e);
+    }
+    // The database is not locked while processing and uploading, both may be slow
+    let mut uploaded = vec![];
+    let mut size = 0;
+    for data in files {
+        let image = store_image(
+            &storage,
+            &image_config,
+            activity_id,
+            member_id,
+            data.to_vec(),
+        )
+        .await;
+        if let Err((code, e)) = image {
+            discard_images(&storage, &uploaded).await;
+            return create_error(code, e);
+        }
+        let (image, image_size) = image.unwrap();
+        uploaded.push(image);
+        size += image_size;
+    }
+    let saved = save_images(&db, &image_config, activity_id, member_id, &uploaded, size).await;
+    if let Err((code, e)) = saved {
+        discard_images(&storage, &uploaded).await;
+        return create_error(code, e);
+    }
+    let response: SuccessResponse<Vec<UploadedImage>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: uploaded,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
+
+/// Strips, resizes and stores one image with its renditions. Returns the
+/// keys and the size of the stored original.
+async fn store_image(
+    storage: &Arc<dyn Storage>,
+    config: &ImageConfig,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+    data: Vec<u8>,
+) -> Result<(UploadedImage, u64), (StatusCode, String)> {
+    let mime = detect_mime(&data);
+    if let Err(e) = mime {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+    let mime = mime.unwrap();
+    if !ALLOWED_IMAGE_TYPES.contains(&mime.as_str()) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported image type: {}", mime),
+        ));
+    }
+    let data = strip_metadata(&mime, &data);
+    if let Err(e) = data {
+        return Err((StatusCode::BAD_REQUEST, e));
+    }
+    let data = data.unwrap();
+    let thumbnail_size = config.thumbnail_size;
+    let preview_size = config.preview_size;
+    let source = data.clone();
+    let renditions = tokio::task::spawn_blocking(move || {
+        create_renditions(&source, thumbnail_size, preview_size)
+    })
+    .await;
+    if renditions.is_err() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to process image".to_string(),
+        ));
+    }
+    let renditions = renditions.unwrap();
+    if let Err(e) = renditions {
+        return Err((StatusCode::BAD_REQUEST, e));
+    }
+    let renditions = renditions.unwrap();
+    let key = format!(
+        "members/{}/{}/{}.{}",
+        activity_id.to_hex(),
+        member_id.to_hex(),
+        Uuid::new_v4(),
+        image_extension(&mime)
+    );
+    let (thumbnail, preview) = rendition_keys(&key);
+    let image = UploadedImage {
+        key,
+        thumbnail,
+        preview,
+    };
+    let size = data.len() as u64;
+    for (key, content_type, data) in [
+        (&image.key, mime.as_str(), data),
+        (&image.thumbnail, "image/webp", renditions.thumbnail),
+        (&image.preview, "image/webp", renditions.preview),
+    ] {
+        if let Err(e) = storage.put(key, content_type, data).await {
+            discard_images(storage, std::slice::from_ref(&image)).await;
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+    }
+    Ok((image, size))
+}
+
+/// Adds stored images to the member, if they still fit in the quota.
+async fn save_images(
+    db: &Arc<Mutex<Database>>,
+    config: &ImageConfig,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+    uploaded: &[UploadedImage],
+    size: u64,
+) -> Result<(), (StatusCode, String)> {
+    let db = db.lock().await;
+    // Check the quota again, another upload may have finished meanwhile
+    let member = find_member_locked(&db, activity_id, member_id).await?;
+    if let Err(e) = check_quota(config, &member, uploaded.len(), size) {
+        return Err((StatusCode::PAYLOAD_TOO_LARGE, e));
+    }
+    let mut images = member.images.unwrap_or_default();
+    images.extend(uploaded.iter().map(|image| image.key.clone()));
+    let image_size = member.image_size.unwrap_or(0) + size;
+    let collection: Collection<Activity> = db.collection("activities");
+    let result = collection
+        .update_one(
+            doc! {"_id": activity_id, "members._id": member_id},
+            doc! {"$set": {
+                "members.$.images": images,
+                "members.$.imageSize": image_size as i64,
+            }},
+            None,
+        )
+        .await;
+    if result.is_err() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update member images".to_string(),
+        ));
+    }
+    if result.unwrap().modified_count != 1 {
+        return Err((StatusCode::NOT_FOUND, "Member not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Deletes the objects of images that will not be attached to the member.
+async fn discard_images(storage: &Arc<dyn Storage>, images: &[UploadedImage]) {
+    for image in images {
+        for key in [&image.key, &image.thumbnail, &image.preview] {
+            // Objects that were never stored fail here, which is fine
+            let _ = storage.delete(key).await;
+        }
+    }
+}
+
+async fn find_member(
+    db: &Arc<Mutex<Database>>,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+) -> Result<ActivityMember, (StatusCode, String)> {
+    let db = db.lock().await;
+    find_member_locked(&db, activity_id, member_id).await
+}
+
+async fn find_member_locked(
+    db: &Database,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+) -> Result<ActivityMember, (StatusCode, String)> {
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity = collection
+        .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+        .await;
+    if activity.is_err() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        ));
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Activity not found".to_string()));
+    }
+    let member = activity
+        .unwrap()
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Member not found".to_string()));
+    }
+    Ok(member.unwrap())
+}
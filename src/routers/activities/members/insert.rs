@@ -1,10 +1,16 @@
 use crate::{
     models::{
-        activities::{Activity, ActivityMember},
-        groups::GroupPermission,
+        activities::{Activity, ActivityType},
+        attendances::{ActivityMember, AttendanceAction, AttendanceMode, AttendanceStatus},
+        groups::{Group, GroupPermission},
         response::{create_error, ResponseStatus, SuccessResponse},
+        users::User,
+    },
+    routers::activities::members::history::create_history,
+    utils::{
+        attendances::duration::check_duration, config::Config, groups::classes::find_user_classes,
+        jwt::UserData,
     },
-    utils::jwt::UserData,
 };
 use axum::{
     extract::{Extension, Json, Path},
@@ -12,28 +18,69 @@ use axum::{
     response::IntoResponse,
 };
 use bson::{doc, oid::ObjectId};
-use mongodb::Database;
-use std::{str::FromStr, sync::Arc};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, str::FromStr, sync::Arc};
 use tokio::sync::Mutex;
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InsertActivityMembers {
+    pub members: Option<Vec<String>>,
+    pub group: Option<String>,
+    pub status: Option<AttendanceStatus>,
+    pub mode: Option<AttendanceMode>,
+    pub duration: Option<f64>,
+    /// Lets an admin credit a duration outside the configured rules.
+    #[serde(rename = "override")]
+    pub override_rules: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InsertActivityMember {
+    #[serde(flatten)]
+    pub member: ActivityMember,
+    /// Lets an admin credit a duration outside the configured rules.
+    #[serde(rename = "override")]
+    pub override_rules: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum InsertMemberResult {
+    Inserted,
+    Duplicated,
+    NotFound,
+    Forbidden,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct InsertMemberReport {
+    pub member: String,
+    pub result: InsertMemberResult,
+}
+
 pub async fn insert_member_into_activity(
     Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
     user: UserData,
     Path(id): Path<String>,
-    Json(activity_member): Json<ActivityMember>,
+    Json(insert): Json<InsertActivityMember>,
 ) -> impl IntoResponse {
+    let mut activity_member = insert.member;
     let db = db.lock().await;
     let collection = db.collection("activities");
     let activity_id = ObjectId::from_str(&id).unwrap();
     let activity = collection.find_one(doc! {"_id": activity_id}, None).await;
-    if let Err(_) = activity {
+    if activity.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find activity".to_string(),
         );
     }
     let activity = activity.unwrap();
-    if let None = activity {
+    if activity.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
     }
     let activity: Activity = bson::from_document(activity.unwrap()).unwrap();
@@ -51,13 +98,41 @@ pub async fn insert_member_into_activity(
     } else {
         return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
     }
+    let overridden = check_duration(
+        &config.durations.clone().unwrap_or_default(),
+        &activity.activity_type,
+        &activity_member.mode,
+        activity_member.duration,
+        insert.override_rules.unwrap_or(false),
+        &user.perms,
+    );
+    if let Err((code, e)) = overridden {
+        return create_error(code, e);
+    }
+    if overridden.unwrap() {
+        let user_id = ObjectId::from_str(&user.id);
+        if user_id.is_err() {
+            return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+        }
+        let mut entry = create_history(
+            &activity_member,
+            user_id.unwrap(),
+            AttendanceAction::Duration,
+        );
+        entry.overridden = Some(true);
+        activity_member
+            .history
+            .get_or_insert_with(Vec::new)
+            .push(entry);
+    }
     let member = bson::to_document(&activity_member);
-    if let Err(_) = member {
+    if member.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid member".to_string());
     }
+    // Another request may have enrolled the member since it was read
     let result = collection
         .update_one(
-            doc! {"_id": activity_id},
+            doc! {"_id": activity_id, "members._id": {"$ne": activity_member._id}},
             doc! {
                 "$push": {
                     "members": member.unwrap()
@@ -66,7 +141,13 @@ pub async fn insert_member_into_activity(
             None,
         )
         .await;
-    if let Ok(_) = result {
+    if result
+        .as_ref()
+        .is_ok_and(|result| result.matched_count == 0)
+    {
+        return create_error(StatusCode::BAD_REQUEST, "Member already exists".to_string());
+    }
+    if result.is_ok() {
         let response: SuccessResponse<_, ()> = SuccessResponse {
             status: ResponseStatus::Success,
             code: 200,
@@ -76,9 +157,258 @@ pub async fn insert_member_into_activity(
         let response = serde_json::to_string(&response).unwrap();
         (StatusCode::OK, Json(response))
     } else {
-        return create_error(
+        create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to insert member".to_string(),
+        )
+    }
+}
+
+fn default_mode(activity_type: &ActivityType) -> AttendanceMode {
+    match activity_type {
+        ActivityType::Social => AttendanceMode::OffCampus,
+        ActivityType::Scale => AttendanceMode::SocialPractice,
+        _ => AttendanceMode::OnCampus,
+    }
+}
+
+/// Decides whether `candidate` can be enrolled. `groups` are the candidate's
+/// groups, or none if there is no such user; secretaries, who are not
+/// managers, may only enrol students of their own `classes`.
+pub fn classify_member(
+    candidate: ObjectId,
+    existing: &HashSet<ObjectId>,
+    seen: &mut HashSet<ObjectId>,
+    groups: Option<&[ObjectId]>,
+    is_manager: bool,
+    classes: &[ObjectId],
+) -> InsertMemberResult {
+    if existing.contains(&candidate) || !seen.insert(candidate) {
+        return InsertMemberResult::Duplicated;
+    }
+    match groups {
+        None => InsertMemberResult::NotFound,
+        Some(groups) if !is_manager && !groups.iter().any(|g| classes.contains(g)) => {
+            InsertMemberResult::Forbidden
+        }
+        Some(_) => InsertMemberResult::Inserted,
+    }
+}
+
+pub async fn insert_members_into_activity(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path(id): Path<String>,
+    Json(request): Json<InsertActivityMembers>,
+) -> impl IntoResponse {
+    let is_manager = user.perms.contains(&GroupPermission::Admin)
+        || user.perms.contains(&GroupPermission::Department);
+    if !is_manager && !user.perms.contains(&GroupPermission::Secretary) {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let activity_id = ObjectId::from_str(&id);
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let user_id = ObjectId::from_str(&user.id);
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let users_collection: Collection<User> = db.collection("users");
+    let groups_collection: Collection<Group> = db.collection("groups");
+    let activity = collection.find_one(doc! {"_id": activity_id}, None).await;
+    if activity.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        );
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
+    }
+    let activity = activity.unwrap();
+    if !is_manager && activity.activity_type == ActivityType::Special {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    // Secretaries may only enrol students of their own classes
+    let classes: Vec<ObjectId> = if is_manager {
+        vec![]
+    } else {
+        let classes = find_user_classes(&db, user_id).await;
+        if let Err(e) = classes {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to validate user: {}", e),
+            );
+        }
+        classes
+            .unwrap()
+            .into_iter()
+            .map(|group| group._id)
+            .collect()
+    };
+    let mut reports: Vec<InsertMemberReport> = vec![];
+    let mut candidates: Vec<ObjectId> = vec![];
+    if let Some(group) = request.group {
+        let group_id = ObjectId::from_str(&group);
+        if group_id.is_err() {
+            return create_error(StatusCode::BAD_REQUEST, "Invalid group ID".to_string());
+        }
+        let group_id = group_id.unwrap();
+        if !is_manager && !classes.contains(&group_id) {
+            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        }
+        let group = groups_collection
+            .find_one(doc! {"_id": group_id, "type": "class"}, None)
+            .await;
+        if group.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to find class".to_string(),
+            );
+        }
+        if group.unwrap().is_none() {
+            return create_error(StatusCode::NOT_FOUND, "Class not found".to_string());
+        }
+        let users = users_collection.find(doc! {"group": group_id}, None).await;
+        if users.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to find class members".to_string(),
+            );
+        }
+        let users: Result<Vec<User>, _> = users.unwrap().try_collect().await;
+        if users.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to find class members".to_string(),
+            );
+        }
+        candidates.extend(users.unwrap().into_iter().map(|user| user._id));
+    }
+    for member in request.members.unwrap_or_default() {
+        match ObjectId::from_str(&member) {
+            Ok(member) => candidates.push(member),
+            Err(_) => reports.push(InsertMemberReport {
+                member,
+                result: InsertMemberResult::Invalid,
+            }),
+        }
+    }
+    if candidates.is_empty() && reports.is_empty() {
+        return create_error(StatusCode::BAD_REQUEST, "No members to insert".to_string());
+    }
+    let users = users_collection
+        .find(doc! {"_id": {"$in": &candidates}}, None)
+        .await;
+    if users.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find users".to_string(),
+        );
+    }
+    let users: Result<Vec<User>, _> = users.unwrap().try_collect().await;
+    if users.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find users".to_string(),
+        );
+    }
+    let users = users.unwrap();
+    let existing: HashSet<ObjectId> = activity
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .map(|member| member._id)
+        .collect();
+    let status = if is_manager {
+        request.status.unwrap_or(AttendanceStatus::Draft)
+    } else {
+        AttendanceStatus::Draft
+    };
+    let mode = request
+        .mode
+        .unwrap_or(default_mode(&activity.activity_type));
+    let duration = request.duration.unwrap_or(0.0);
+    let overridden = check_duration(
+        &config.durations.clone().unwrap_or_default(),
+        &activity.activity_type,
+        &mode,
+        duration,
+        request.override_rules.unwrap_or(false),
+        &user.perms,
+    );
+    if let Err((code, e)) = overridden {
+        return create_error(code, e);
+    }
+    let overridden = overridden.unwrap();
+    let mut seen: HashSet<ObjectId> = HashSet::new();
+    for candidate in candidates {
+        let groups = users
+            .iter()
+            .find(|user| user._id == candidate)
+            .map(|user| user.group.as_slice());
+        let mut result = classify_member(
+            candidate, &existing, &mut seen, groups, is_manager, &classes,
         );
+        if result == InsertMemberResult::Inserted {
+            let mut member = ActivityMember {
+                _id: candidate,
+                status: status.clone(),
+                impression: None,
+                duration,
+                mode: mode.clone(),
+                history: Some(vec![]),
+                images: Some(vec![]),
+                image_size: Some(0),
+            };
+            if overridden {
+                let mut entry = create_history(&member, user_id, AttendanceAction::Duration);
+                entry.overridden = Some(true);
+                member.history = Some(vec![entry]);
+            }
+            let member = bson::to_document(&member);
+            if member.is_err() {
+                return create_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to serialize member".to_string(),
+                );
+            }
+            // Pushes only if another request has not enrolled the member since
+            let inserted = collection
+                .update_one(
+                    doc! {"_id": activity_id, "members._id": {"$ne": candidate}},
+                    doc! {"$push": {"members": member.unwrap()}},
+                    None,
+                )
+                .await;
+            if inserted.is_err() {
+                return create_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to insert members".to_string(),
+                );
+            }
+            if inserted.unwrap().matched_count == 0 {
+                result = InsertMemberResult::Duplicated;
+            }
+        }
+        reports.push(InsertMemberReport {
+            member: candidate.to_hex(),
+            result,
+        });
     }
+    let response: SuccessResponse<Vec<InsertMemberReport>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: reports,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
 }
@@ -1,4 +1,5 @@
 pub mod history;
+pub mod images;
 pub mod insert;
 pub mod read;
 pub mod remove;
@@ -1,10 +1,11 @@
 use crate::{
     models::{
-        activities::{Activity, ActivityMember},
+        activities::Activity,
+        attendances::ActivityMember,
         groups::GroupPermission,
         response::{create_error, ResponseStatus, SuccessResponse},
     },
-    utils::{groups::same_class, jwt::UserData},
+    utils::{groups::classes::share_class, jwt::UserData},
 };
 use axum::{
     extract::{Extension, Json, Path},
@@ -16,57 +17,69 @@ use mongodb::Database;
 use std::{str::FromStr, sync::Arc};
 use tokio::sync::Mutex;
 
+/// Whether `user` may see the member's record: staff, the member themself, or
+/// a Secretary of one of the member's classes.
+pub async fn can_read_member(
+    db: &Database,
+    user: &UserData,
+    member_id: ObjectId,
+) -> Result<bool, String> {
+    if user.perms.contains(&GroupPermission::Admin)
+        || user.perms.contains(&GroupPermission::Department)
+        || user.perms.contains(&GroupPermission::Auditor)
+        || user.id == member_id.to_hex()
+    {
+        return Ok(true);
+    }
+    if !user.perms.contains(&GroupPermission::Secretary) {
+        return Ok(false);
+    }
+    let user_id = ObjectId::from_str(&user.id);
+    if user_id.is_err() {
+        return Err("Invalid user ID".to_string());
+    }
+    share_class(db, user_id.unwrap(), member_id).await
+}
+
 pub async fn read_member(
     Extension(db): Extension<Arc<Mutex<Database>>>,
     user: UserData,
-    Path(id): Path<String>,
-    Path(member_id): Path<String>,
+    Path((id, member_id)): Path<(String, String)>,
 ) -> impl IntoResponse {
-    let db_clone = db.clone();
     let db = db.lock().await;
     let collection = db.collection("activities");
     let activity_id = ObjectId::from_str(id.as_str());
-    if let Err(_) = activity_id {
+    if activity_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
     }
     let activity_id = activity_id.unwrap();
     let member_id = ObjectId::from_str(member_id.as_str());
-    if let Err(_) = member_id {
+    if member_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
     }
     let member_id = member_id.unwrap();
     let activity = collection
         .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
         .await;
-    if let Err(_) = activity {
+    if activity.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find activity".to_string(),
         );
     }
     let activity = activity.unwrap();
-    if let None = activity {
+    if activity.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
     }
     let activity: Activity = bson::from_document(activity.unwrap()).unwrap();
-    if user.perms.contains(&GroupPermission::Admin)
-        || user.perms.contains(&GroupPermission::Department)
-        || user.perms.contains(&GroupPermission::Auditor)
-        || user.id == member_id.clone().to_string()
-    {
-    } else if user.perms.contains(&GroupPermission::Secretary) {
-        let user_id = ObjectId::from_str(&user.id).unwrap();
-        let same = same_class::validate_same_class(db_clone, member_id, user_id).await;
-        if let Err(e) = same {
-            return create_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to validate user: {}", e),
-            );
-        }
-        if !same.unwrap() {
-            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
-        }
-    } else {
+    let allowed = can_read_member(&db, &user, member_id).await;
+    if let Err(e) = allowed {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to validate user: {}", e),
+        );
+    }
+    if !allowed.unwrap() {
         return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
     }
     let member = activity
@@ -74,7 +87,7 @@ pub async fn read_member(
         .unwrap_or_default()
         .into_iter()
         .find(|member| member._id == member_id);
-    if let None = member {
+    if member.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
     }
     let member = member.unwrap();
@@ -1 +1,115 @@
+use crate::{
+    models::{
+        activities::{Activity, ActivityStatus},
+        attendances::{AttendanceAction, AttendanceStatus},
+        groups::GroupPermission,
+        response::{create_error, ResponseStatus, SuccessResponse},
+    },
+    routers::activities::members::history::create_history,
+    utils::jwt::UserData,
+};
+use axum::{
+    extract::{Extension, Json, Path},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use bson::{doc, oid::ObjectId};
+use mongodb::{Collection, Database};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
 
+pub async fn remove_member_from_activity(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Path((id, member_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
+    }
+    let member_id = member_id.unwrap();
+    let user_id = ObjectId::from_str(user.id.as_str());
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let activity = collection
+        .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+        .await;
+    if activity.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        );
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
+    }
+    let activity = activity.unwrap();
+    let member = activity
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
+    }
+    let mut member = member.unwrap();
+    if user.perms.contains(&GroupPermission::Admin)
+        || user.perms.contains(&GroupPermission::Department)
+        || (activity.creator == user_id && activity.status == ActivityStatus::Pending)
+        || (member_id == user_id && member.status == AttendanceStatus::Draft)
+    {
+    } else {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    // Keep the removed member with its history so the removal can be audited
+    let history = create_history(&member, user_id, AttendanceAction::Remove);
+    member.history.get_or_insert_with(Vec::new).push(history);
+    let member = bson::to_document(&member);
+    if member.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to serialize member".to_string(),
+        );
+    }
+    let result = collection
+        .update_one(
+            doc! {"_id": activity_id, "members._id": member_id},
+            doc! {
+                "$pull": {"members": {"_id": member_id}},
+                "$push": {"removedMembers": member.unwrap()},
+            },
+            None,
+        )
+        .await;
+    if result.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to remove member".to_string(),
+        );
+    }
+    let result = result.unwrap();
+    if result.modified_count != 1 {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to remove member".to_string(),
+        );
+    }
+    let response: SuccessResponse<_, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: (),
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
@@ -1,10 +1,22 @@
 use crate::{
     models::{
-        activities::{Activity, ActivityMember, ActivityMemberStatus, ActivityMode},
+        activities::Activity,
+        attendances::{ActivityMember, AttendanceAction, AttendanceMode, AttendanceStatus},
         groups::GroupPermission,
         response::{create_error, ResponseStatus, SuccessResponse},
     },
-    utils::jwt::UserData,
+    routers::activities::members::history::{create_history, record_history},
+    utils::{
+        attendances::{
+            duration::check_duration,
+            transition::{transition_roles, validate_transition, TransitionError},
+        },
+        config::Config,
+        impressions::{
+            check_impression, find_related_impressions, rejection_message, ImpressionIssue,
+        },
+        jwt::UserData,
+    },
 };
 use axum::{
     extract::{Extension, Json, Path},
@@ -12,7 +24,7 @@ use axum::{
     response::IntoResponse,
 };
 use bson::{doc, oid::ObjectId};
-use futures::StreamExt;
+use futures::TryStreamExt;
 use mongodb::{Collection, Database};
 use serde::{Deserialize, Serialize};
 use std::{str::FromStr, sync::Arc};
@@ -20,13 +32,20 @@ use tokio::sync::Mutex;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 pub struct UpdateActivityMemberMode {
-    pub mode: ActivityMode,
+    pub mode: AttendanceMode,
+    /// Lets an admin keep a duration the new mode does not allow.
+    #[serde(rename = "override")]
+    pub override_rules: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct UpdateActivityMemberStatus {
-    pub status: ActivityMemberStatus,
+    pub status: AttendanceStatus,
     pub duration: Option<f64>,
+    pub reason: Option<String>,
+    /// Lets an admin credit a duration outside the configured rules.
+    #[serde(rename = "override")]
+    pub override_rules: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
@@ -34,118 +53,195 @@ pub struct UpdateActivityMemberImpression {
     pub impression: String,
 }
 
+/// Checks an impression against the member's other impressions and those of
+/// the activity, returning every issue found. Only submitting rejects an
+/// impression, so drafts can be saved while they are still being written.
+async fn find_impression_issues(
+    db: &Database,
+    config: &Config,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+    impression: &str,
+) -> Result<Vec<ImpressionIssue>, (StatusCode, String)> {
+    let related = find_related_impressions(db, activity_id, member_id).await;
+    if let Err(e) = related {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+    let (activity_impressions, history_impressions) = related.unwrap();
+    Ok(check_impression(
+        &config.impressions.clone().unwrap_or_default(),
+        impression,
+        &activity_impressions,
+        &history_impressions,
+    ))
+}
+
 pub async fn update_member_status(
     Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
     user: UserData,
-    Path(id): Path<String>,
-    Path(member_id): Path<String>,
+    Path((id, member_id)): Path<(String, String)>,
     Json(update): Json<UpdateActivityMemberStatus>,
 ) -> impl IntoResponse {
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
-    let activity_id = ObjectId::from_str(&id.as_str());
-    if let Err(_) = activity_id {
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
     }
     let activity_id = activity_id.unwrap();
-    let member_id = ObjectId::from_str(&member_id.as_str());
-    if let Err(_) = member_id {
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
     }
     let member_id = member_id.unwrap();
+    let user_id = ObjectId::from_str(user.id.as_str());
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
     let activity = collection
         .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
         .await;
-    if let Err(_) = activity {
+    if activity.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find activity".to_string(),
         );
     }
     let activity = activity.unwrap();
-    if let None = activity {
+    if activity.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
     }
     let activity: Activity = activity.unwrap();
-    let members = activity.members;
-    if let None = members {
-        return create_error(StatusCode::NOT_FOUND, "Activity has no members".to_string());
-    }
-    let mut members = members.unwrap();
-    for member in members.iter_mut() {
-        if member._id == member_id {
-            if member.status == ActivityMemberStatus::Effective
-                || member.status == ActivityMemberStatus::Refused
-            {
-                return create_error(
-                    StatusCode::FORBIDDEN,
-                    "Cannot update member status".to_string(),
-                );
-            } else if member.status == ActivityMemberStatus::Pending
-                && !user.perms.contains(&GroupPermission::Auditor)
-                && !user.perms.contains(&GroupPermission::Admin)
-            {
-                return create_error(
-                    StatusCode::FORBIDDEN,
-                    "Cannot update member status".to_string(),
-                );
-            } else if member.status == ActivityMemberStatus::Draft
-                || member.status == ActivityMemberStatus::Rejected
-                    && user.id != member_id.to_string()
-            {
-                return create_error(
-                    StatusCode::FORBIDDEN,
-                    "Cannot update member status".to_string(),
-                );
-            }
-            let status = serde_json::to_string(&update.status).unwrap();
-            let result = collection.update_one(
-                doc! {"_id": activity_id, "members._id": member_id},
-                doc! {"$set": {"members.$.status": status, "members.$.duration": update.duration.unwrap_or(member.duration)}},
-                None,
-            ).await;
-            if let Err(_) = result {
-                return create_error(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to update member status".to_string(),
-                );
-            }
-            let result = result.unwrap();
-            if result.modified_count != 1 {
-                return create_error(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to update member status".to_string(),
-                );
-            }
-            let response: SuccessResponse<Vec<ActivityMember>, ()> = SuccessResponse {
-                status: ResponseStatus::Success,
-                code: 200,
-                data: vec![],
-                metadata: None,
-            };
-            let response = serde_json::to_string(&response).unwrap();
-            return (StatusCode::OK, Json(response));
+    let member = activity
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
+    }
+    let member = member.unwrap();
+    let roles = transition_roles(user_id == member_id, &user.perms);
+    match validate_transition(&member.status, &update.status, &roles) {
+        Ok(()) => {}
+        Err(TransitionError::Invalid) => {
+            return create_error(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Cannot change member status from {:?} to {:?}",
+                    member.status, update.status
+                ),
+            );
+        }
+        Err(TransitionError::Forbidden) => {
+            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        }
+    }
+    // Submitting is where the impression is checked, not only when edited
+    if update.status == AttendanceStatus::Pending && member.status != AttendanceStatus::Pending {
+        let impression = member.impression.clone().unwrap_or_default();
+        if impression.trim().is_empty() {
+            return create_error(
+                StatusCode::BAD_REQUEST,
+                "Write an impression before submitting".to_string(),
+            );
+        }
+        let issues =
+            find_impression_issues(&db, &config, activity_id, member_id, &impression).await;
+        if let Err((code, e)) = issues {
+            return create_error(code, e);
+        }
+        let impression_config = config.impressions.clone().unwrap_or_default();
+        if let Some(message) = rejection_message(&impression_config, &issues.unwrap()) {
+            return create_error(StatusCode::BAD_REQUEST, message);
+        }
+    }
+    let mut overridden = false;
+    if let Some(duration) = update.duration {
+        let checked = check_duration(
+            &config.durations.clone().unwrap_or_default(),
+            &activity.activity_type,
+            &member.mode,
+            duration,
+            update.override_rules.unwrap_or(false),
+            &user.perms,
+        );
+        if let Err((code, e)) = checked {
+            return create_error(code, e);
         }
+        overridden = checked.unwrap();
     }
-    (StatusCode::OK, Json("".to_string()))
+    let status = bson::to_bson(&update.status).unwrap();
+    let mut updated = member.clone();
+    updated.status = update.status.clone();
+    updated.duration = update.duration.unwrap_or(member.duration);
+    let action = if updated.status != member.status {
+        AttendanceAction::Status
+    } else {
+        AttendanceAction::Duration
+    };
+    let mut entry = create_history(&updated, user_id, action);
+    entry.reason = update.reason.clone();
+    entry.overridden = overridden.then_some(true);
+    let set = doc! {
+        "members.$.status": status,
+        "members.$.duration": updated.duration,
+    };
+    let changes = record_history(set, &member, &entry);
+    if changes.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to serialize member history".to_string(),
+        );
+    }
+    let result = collection
+        .update_one(
+            doc! {"_id": activity_id, "members._id": member_id},
+            changes.unwrap(),
+            None,
+        )
+        .await;
+    if result.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update member status".to_string(),
+        );
+    }
+    let result = result.unwrap();
+    if result.modified_count != 1 {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update member status".to_string(),
+        );
+    }
+    let response: SuccessResponse<Vec<ActivityMember>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: vec![],
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
 }
 
 pub async fn update_member_impression(
     Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
     user: UserData,
-    Path(id): Path<String>,
-    Path(member_id): Path<String>,
+    Path((id, member_id)): Path<(String, String)>,
     Json(update): Json<UpdateActivityMemberImpression>,
 ) -> impl IntoResponse {
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
-    let activity_id = ObjectId::from_str(&id.as_str());
-    if let Err(_) = activity_id {
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
     }
     let activity_id = activity_id.unwrap();
-    let member_id = ObjectId::from_str(&member_id.as_str());
-    if let Err(_) = member_id {
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
     }
     let member_id = member_id.unwrap();
@@ -156,46 +252,62 @@ pub async fn update_member_impression(
         doc! {"$match": {"_id": activity_id}},
         doc! {"$unwind": "$members"},
         doc! {"$match": {"members._id": member_id}},
-        doc! {"$project": {"members": 1}},
+        doc! {"$replaceRoot": {"newRoot": "$members"}},
     ];
     let activity = collection.aggregate(pipeline, None).await;
-    if let Err(_) = activity {
+    if activity.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find activity".to_string(),
         );
     }
-    let activity = activity.unwrap().collect::<Vec<_>>().await[0].clone();
+    let activity = activity.unwrap().try_next().await;
     if let Err(e) = activity {
-        return create_error(
-            StatusCode::NOT_FOUND,
-            format!("Activity not found: {}", e.to_string()),
-        );
+        return create_error(StatusCode::NOT_FOUND, format!("Activity not found: {}", e));
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
     }
     let member = bson::from_document::<ActivityMember>(activity.unwrap());
-    if let Err(_) = member {
+    if member.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find member".to_string(),
         );
     }
     let member = member.unwrap();
-    if member.status == ActivityMemberStatus::Effective
-        || member.status == ActivityMemberStatus::Refused
-    {
+    if member.status == AttendanceStatus::Effective || member.status == AttendanceStatus::Refused {
         return create_error(
             StatusCode::FORBIDDEN,
             "Cannot update member impression".to_string(),
         );
     }
+    let issues =
+        find_impression_issues(&db, &config, activity_id, member_id, &update.impression).await;
+    if let Err((code, e)) = issues {
+        return create_error(code, e);
+    }
+    let issues = issues.unwrap();
+    let mut updated = member.clone();
+    updated.impression = Some(update.impression.clone());
+    let entry = create_history(&updated, member_id, AttendanceAction::Impression);
+    let set = doc! {"members.$.impression": update.impression};
+    let changes = record_history(set, &member, &entry);
+    if changes.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to serialize member history".to_string(),
+        );
+    }
     let result = collection
         .update_one(
             doc! {"_id": activity_id, "members._id": member_id},
-            doc! {"$set": {"members.$.impression": update.impression}},
+            changes.unwrap(),
             None,
         )
         .await;
-    if let Err(_) = result {
+    if result.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to update member impression".to_string(),
@@ -208,6 +320,123 @@ pub async fn update_member_impression(
             "Failed to update member impression".to_string(),
         );
     }
+    // Issues are only warned about here, submitting rejects them
+    let response: SuccessResponse<Vec<ImpressionIssue>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: issues,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
+
+/// Auditors and admins may change any member's mode, members only that of
+/// their own drafts.
+pub fn can_update_mode(
+    perms: &[GroupPermission],
+    is_member: bool,
+    status: &AttendanceStatus,
+) -> bool {
+    perms.contains(&GroupPermission::Auditor)
+        || perms.contains(&GroupPermission::Admin)
+        || (is_member && *status == AttendanceStatus::Draft)
+}
+
+pub async fn update_member_mode(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path((id, member_id)): Path<(String, String)>,
+    Json(update): Json<UpdateActivityMemberMode>,
+) -> impl IntoResponse {
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity_id = ObjectId::from_str(id.as_str());
+    if activity_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string());
+    }
+    let activity_id = activity_id.unwrap();
+    let member_id = ObjectId::from_str(member_id.as_str());
+    if member_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid member ID".to_string());
+    }
+    let member_id = member_id.unwrap();
+    let user_id = ObjectId::from_str(user.id.as_str());
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let activity = collection
+        .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+        .await;
+    if activity.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find activity".to_string(),
+        );
+    }
+    let activity = activity.unwrap();
+    if activity.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
+    }
+    let activity = activity.unwrap();
+    let member = activity
+        .members
+        .unwrap_or_default()
+        .into_iter()
+        .find(|member| member._id == member_id);
+    if member.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Member not found".to_string());
+    }
+    let member = member.unwrap();
+    if !can_update_mode(&user.perms, user_id == member_id, &member.status) {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    // The duration was checked against the old mode
+    let overridden = check_duration(
+        &config.durations.clone().unwrap_or_default(),
+        &activity.activity_type,
+        &update.mode,
+        member.duration,
+        update.override_rules.unwrap_or(false),
+        &user.perms,
+    );
+    if let Err((code, e)) = overridden {
+        return create_error(code, e);
+    }
+    let mode = bson::to_bson(&update.mode).unwrap();
+    let mut updated = member.clone();
+    updated.mode = update.mode.clone();
+    let mut entry = create_history(&updated, user_id, AttendanceAction::Mode);
+    entry.overridden = overridden.unwrap().then_some(true);
+    let changes = record_history(doc! {"members.$.mode": mode}, &member, &entry);
+    if changes.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to serialize member history".to_string(),
+        );
+    }
+    let result = collection
+        .update_one(
+            doc! {"_id": activity_id, "members._id": member_id},
+            changes.unwrap(),
+            None,
+        )
+        .await;
+    if result.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update member mode".to_string(),
+        );
+    }
+    let result = result.unwrap();
+    if result.modified_count != 1 {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update member mode".to_string(),
+        );
+    }
     let response: SuccessResponse<Vec<ActivityMember>, ()> = SuccessResponse {
         status: ResponseStatus::Success,
         code: 200,
@@ -2,9 +2,12 @@ use crate::{
     models::{
         activities::Activity,
         groups::GroupPermission,
-        response::{create_error, MetadataSize, ResponseStatus, SuccessResponse},
+        response::{create_error, MetadataPage, ResponseStatus, SuccessResponse},
+    },
+    utils::{
+        cursor::{decode_cursor, encode_cursor},
+        jwt::UserData,
     },
-    utils::jwt::UserData,
 };
 use axum::{
     extract::{Extension, Path, Query},
@@ -23,6 +26,7 @@ pub struct ReadActivityQuery {
     pub page: Option<u32>,
     pub perpage: Option<u32>,
     pub query: Option<String>,
+    pub cursor: Option<String>,
 }
 
 pub async fn read_all(
@@ -32,6 +36,7 @@ pub async fn read_all(
         page,
         perpage,
         query,
+        cursor,
     }): Query<ReadActivityQuery>,
 ) -> impl IntoResponse {
     if user.perms.contains(&GroupPermission::Department)
@@ -41,9 +46,14 @@ pub async fn read_all(
     } else {
         return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
     }
-    let page = page.unwrap_or(1);
-    let perpage = perpage.unwrap_or(10);
+    let page = page.unwrap_or(1).max(1);
+    let perpage = perpage.unwrap_or(10).max(1);
     let query = query.unwrap_or("".to_string());
+    let cursor = cursor.map(|cursor| decode_cursor(&cursor)).transpose();
+    if let Err(e) = cursor {
+        return create_error(StatusCode::BAD_REQUEST, e);
+    }
+    let cursor = cursor.unwrap();
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
     let target = if user.perms.contains(&GroupPermission::Auditor)
@@ -62,11 +72,21 @@ pub async fn read_all(
     if let Err(e) = count {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read activity: {}", e.to_string()),
+            format!("Failed to read activity: {}", e),
         );
     }
+    let mut filter = doc! {"name": {"$regex": query, "$options": "i"}};
+    // With a cursor, continue after the last seen `_id` instead of skipping pages
+    if let Some(cursor) = cursor {
+        filter.insert("_id", doc! {"$lt": cursor});
+    }
+    let skip = if cursor.is_some() {
+        0
+    } else {
+        (page - 1) * perpage
+    };
     let pipeline = vec![
-        doc! {"$match": {"name": {"$regex": query, "$options": "i"}}},
+        doc! {"$match": filter},
         doc! {"$sort": {"_id": -1}},
         doc! {"$project": {
                 "name": 1,
@@ -90,14 +110,14 @@ pub async fn read_all(
             "members.impression": 0,
             "members.images": 0,
         }},
-        doc! {"$skip": (page - 1) * perpage},
+        doc! {"$skip": skip},
         doc! {"$limit": perpage},
     ];
     let cursor = collection.aggregate(pipeline, None).await;
     if let Err(e) = cursor {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read activity: {}", e.to_string()),
+            format!("Failed to read activity: {}", e),
         );
     }
     let mut cursor = cursor.unwrap();
@@ -107,7 +127,7 @@ pub async fn read_all(
         if let Err(e) = doc_result {
             return create_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to read activity: {}", e.to_string()),
+                format!("Failed to read activity: {}", e),
             );
         }
         if let Ok(Some(document)) = doc_result {
@@ -116,7 +136,7 @@ pub async fn read_all(
                 Err(e) => {
                     return create_error(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Failed to read activity: {}", e.to_string()),
+                        format!("Failed to read activity: {}", e),
                     )
                 }
             }
@@ -124,12 +144,20 @@ pub async fn read_all(
             break;
         }
     }
-    let response: SuccessResponse<_, MetadataSize> = SuccessResponse {
+    let next_cursor = if activities.len() == perpage as usize {
+        activities
+            .last()
+            .map(|activity| encode_cursor(&activity._id))
+    } else {
+        None
+    };
+    let response: SuccessResponse<_, MetadataPage> = SuccessResponse {
         status: ResponseStatus::Success,
         code: 200,
         data: activities,
-        metadata: Some(MetadataSize {
+        metadata: Some(MetadataPage {
             size: count.unwrap(),
+            next_cursor,
         }),
     };
     let response = serde_json::to_string(&response).unwrap();
@@ -144,7 +172,7 @@ pub async fn read_one(
     let db = client.lock().await;
     let collection = db.collection("activities");
     let id = ObjectId::parse_str(&id);
-    if let Err(_) = id {
+    if id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid ID".to_string());
     }
     let id = id.unwrap();
@@ -153,6 +181,7 @@ pub async fn read_one(
         "members.history": 0,
         "members.impression": 0,
         "members.images": 0,
+        "removedMembers": 0,
     };
     let result = collection
         .find_one(
@@ -163,7 +192,7 @@ pub async fn read_one(
     if let Err(e) = result {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to read activity: {}", e.to_string()),
+            format!("Failed to read activity: {}", e),
         );
     }
     let result: Option<Activity> = result.unwrap();
@@ -178,8 +207,6 @@ pub async fn read_one(
             let response = serde_json::to_string(&response).unwrap();
             (StatusCode::OK, Json(response))
         }
-        None => {
-            return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
-        }
+        None => create_error(StatusCode::NOT_FOUND, "Activity not found".to_string()),
     }
 }
@@ -24,7 +24,7 @@ pub async fn remove_activity(
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
     let id = ObjectId::parse_str(&id);
-    if let Err(_) = id {
+    if id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity id".to_string());
     }
     let id = id.unwrap();
@@ -36,11 +36,11 @@ pub async fn remove_activity(
         if let Err(e) = activity {
             return create_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to find activity: {}", e.to_string()),
+                format!("Failed to find activity: {}", e),
             );
         }
         let activity = activity.unwrap();
-        if let None = activity {
+        if activity.is_none() {
             return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
         }
         let activity = activity.unwrap();
@@ -54,7 +54,7 @@ pub async fn remove_activity(
     if let Err(e) = result {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to delete activity: {}", e.to_string()),
+            format!("Failed to delete activity: {}", e),
         );
     }
     let result = result.unwrap();
@@ -66,8 +66,7 @@ pub async fn remove_activity(
         code: 200,
         data: (),
         metadata: None,
-    }
-    .into();
+    };
     let response = serde_json::to_string(&response).unwrap();
     (StatusCode::OK, Json(response))
 }
@@ -31,7 +31,7 @@ pub async fn update_activity_name(
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
     let id = ObjectId::parse_str(&id);
-    if let Err(_) = id {
+    if id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity id".to_string());
     }
     let id = id.unwrap();
@@ -39,11 +39,11 @@ pub async fn update_activity_name(
     if let Err(e) = activity {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to find activity: {}", e.to_string()),
+            format!("Failed to find activity: {}", e),
         );
     }
     let activity = activity.unwrap();
-    if let None = activity {
+    if activity.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
     }
     let activity = activity.unwrap();
@@ -58,7 +58,7 @@ pub async fn update_activity_name(
         if let Err(e) = result {
             return create_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to update activity: {}", e.to_string()),
+                format!("Failed to update activity: {}", e),
             );
         }
         let response: SuccessResponse<Vec<Activity>, ()> = SuccessResponse {
@@ -68,9 +68,9 @@ pub async fn update_activity_name(
             metadata: None,
         };
         let response = serde_json::to_string(&response).unwrap();
-        return (StatusCode::OK, Json(response));
+        (StatusCode::OK, Json(response))
     } else {
-        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
     }
 }
 
@@ -88,7 +88,7 @@ pub async fn update_activity_description(
     let db = db.lock().await;
     let collection: Collection<Activity> = db.collection("activities");
     let id = ObjectId::parse_str(&id);
-    if let Err(_) = id {
+    if id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid activity id".to_string());
     }
     let id = id.unwrap();
@@ -96,11 +96,11 @@ pub async fn update_activity_description(
     if let Err(e) = activity {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to find activity: {}", e.to_string()),
+            format!("Failed to find activity: {}", e),
         );
     }
     let activity = activity.unwrap();
-    if let None = activity {
+    if activity.is_none() {
         return create_error(StatusCode::NOT_FOUND, "Activity not found".to_string());
     }
     let activity = activity.unwrap();
@@ -119,7 +119,7 @@ pub async fn update_activity_description(
         if let Err(e) = result {
             return create_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to update activity: {}", e.to_string()),
+                format!("Failed to update activity: {}", e),
             );
         }
         let response: SuccessResponse<Vec<Activity>, ()> = SuccessResponse {
@@ -129,12 +129,13 @@ pub async fn update_activity_description(
             metadata: None,
         };
         let response = serde_json::to_string(&response).unwrap();
-        return (StatusCode::OK, Json(response));
+        (StatusCode::OK, Json(response))
     } else {
-        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct UpdateActivityStatus {
     status: ActivityStatus,
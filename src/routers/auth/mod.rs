@@ -40,13 +40,13 @@ pub async fn login(
 ) -> impl IntoResponse {
     let client = client.lock().await;
     let collection = client.collection("users");
-    let id = ObjectId::from_str(&body.userid.as_str());
-    if let Err(_) = id {
+    let id = ObjectId::from_str(body.userid.as_str());
+    if id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid user id".to_string());
     }
     let id = id.unwrap();
     let user = collection.find_one(Some(doc! {"_id": id}), None).await;
-    if let Err(_) = user {
+    if user.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to find user".to_string(),
@@ -56,27 +56,26 @@ pub async fn login(
     if let Some(user) = user {
         let keypair = load_keypair().await;
         let credentials = hex::decode(&body.credentials);
-        if let Err(_) = credentials {
+        if credentials.is_err() {
             return create_error(StatusCode::BAD_REQUEST, "Invalid credentials".to_string());
         }
         let credentials = credentials.unwrap();
         let credentials = decrypt(&keypair.0, &credentials).await;
         let credentials = serde_json::from_str(&credentials);
-        if let Err(_) = credentials {
+        if credentials.is_err() {
             return create_error(StatusCode::BAD_REQUEST, "Invalid credentials".to_string());
         }
         let credentials: LoginCredentials = credentials.unwrap();
         if user.clone().valid_password(credentials.password).await {
             let groups = client.collection("groups");
             let token = user.generate_token(&collection, &groups, body.term).await;
-            if let Err(_) = token {
+            if token.is_err() {
                 return create_error(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Failed to generate token".to_string(),
                 );
             }
             let token = token.unwrap();
-            println!("{:?}", token.clone());
             let response: SuccessResponse<String, ()> = SuccessResponse {
                 status: ResponseStatus::Success,
                 code: 200,
@@ -86,9 +85,9 @@ pub async fn login(
             let response = json!(response).to_string();
             (StatusCode::OK, Json(response))
         } else {
-            return create_error(StatusCode::UNAUTHORIZED, "Invalid credentials".to_string());
+            create_error(StatusCode::UNAUTHORIZED, "Invalid credentials".to_string())
         }
     } else {
-        return create_error(StatusCode::NOT_FOUND, "User not found".to_string());
+        create_error(StatusCode::NOT_FOUND, "User not found".to_string())
     }
 }
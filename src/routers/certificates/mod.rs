@@ -0,0 +1,46 @@
+use crate::{
+    models::{
+        certificates::{Certificate, CertificateVerification},
+        response::{create_error, ResponseStatus, SuccessResponse},
+    },
+    utils::certificates::normalize_code,
+};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use bson::doc;
+use mongodb::{Collection, Database};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Looks up an issued certificate by its code. This is public so schools and
+/// universities can check a certificate without an account.
+pub async fn verify_certificate(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Path(code): Path<String>,
+) -> impl IntoResponse {
+    let code = normalize_code(&code);
+    let db = db.lock().await.clone();
+    let collection: Collection<Certificate> = db.collection("certificates");
+    let certificate = collection.find_one(doc! {"code": &code}, None).await;
+    if certificate.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find certificate".to_string(),
+        );
+    }
+    let certificate = certificate.unwrap();
+    if certificate.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Certificate not found".to_string());
+    }
+    let response: SuccessResponse<CertificateVerification, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: StatusCode::OK.as_u16(),
+        data: certificate.unwrap().into(),
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
@@ -1,30 +1,87 @@
+pub mod schedules;
+
 use axum::{
+    body::Body,
     extract::{Extension, Path},
-    http::StatusCode,
-    response::{IntoResponse, Json},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
-use bson::oid::ObjectId;
+use bson::{doc, oid::ObjectId, Document};
 use mongodb::Database;
-use std::{fs, str::FromStr, sync::Arc, time::SystemTime};
+use serde_json::Value;
+use socketioxide::{
+    extract::{Data, SocketRef},
+    SocketIo,
+};
+use std::{str::FromStr, sync::Arc, time::SystemTime};
 use tempfile::NamedTempFile;
-use tokio::sync::Mutex;
+use tokio::{
+    fs,
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
     models::{
-        exports::{ExportActivityTimesOptions, ExportState, Task, TaskStatus},
+        exports::{
+            ExportActivityTimesOptions, ExportFormat, ExportKind, ExportRosterOptions, Task,
+            TaskStatus,
+        },
         groups::GroupPermission,
         response::create_error,
     },
     utils::{
-        exports::{csv_to_excel, export_csv},
-        jwt::UserData,
+        config::Config,
+        dates::{config_timezone, DateRange},
+        exports::{export_csv, export_excel, export_json, export_roster, tasks},
+        groups::classes::find_user_classes,
+        jwt::{verify_token, UserData},
     },
 };
 
+/// Clients emit this with a task ID to receive its progress.
+pub const EXPORT_SUBSCRIBE_EVENT: &str = "export-subscribe";
+/// Emitted with the whole task whenever its state or percentage changes.
+pub const EXPORT_PROGRESS_EVENT: &str = "export-progress";
+
+/// Reads the user from the token a client sends in its connection auth data,
+/// e.g. `{"token": "..."}`.
+pub fn socket_user(auth: &Value) -> Option<UserData> {
+    let token = auth.get("token")?.as_str()?;
+    let token = verify_token(token.to_string()).ok()?;
+    Some(UserData {
+        id: token.sub,
+        perms: token.perms,
+        term: token.term,
+    })
+}
+
+/// Lets the socket follow tasks its user may access, with the same rule as
+/// reading the task over HTTP. Sockets without a valid token cannot subscribe.
+pub fn subscribe_export(socket: &SocketRef, auth: &Value, db: Arc<Mutex<Database>>) {
+    let user = socket_user(auth);
+    if user.is_none() {
+        return;
+    }
+    let user = user.unwrap();
+    socket.on(
+        EXPORT_SUBSCRIBE_EVENT,
+        move |socket: SocketRef, Data::<String>(task_id)| async move {
+            let db = db.lock().await.clone();
+            if find_own_task(&db, &user, &task_id).await.is_ok() {
+                socket.join(task_id).ok();
+            }
+        },
+    );
+}
+
 pub async fn export_activity_times(
     Extension(db): Extension<Arc<Mutex<Database>>>,
-    Extension(exporters): Extension<Arc<ExportState>>,
+    Extension(config): Extension<Arc<Config>>,
+    Extension(io): Extension<SocketIo>,
     user: UserData,
     Json(options): Json<ExportActivityTimesOptions>,
 ) -> impl IntoResponse {
@@ -35,11 +92,79 @@ pub async fn export_activity_times(
             .into_response();
     }
 
-    let task_id = Uuid::new_v4();
-    println!(
-        "Received task to export activity times, job ID: {}",
-        task_id
-    );
+    if let Err(e) = ObjectId::from_str(&user.id) {
+        return create_error(StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e))
+            .into_response();
+    }
+    let user_id = ObjectId::from_str(&user.id).unwrap();
+
+    info!("Starting to export activity times by user {}", user_id);
+
+    start_task(db, config, io, user_id, ExportKind::ActivityTimes, options).await
+}
+
+/// Exports the members of the given activities, or of every activity in the
+/// date range, for sign-off forms.
+pub async fn export_roster(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    Extension(io): Extension<SocketIo>,
+    user: UserData,
+    Json(roster): Json<ExportRosterOptions>,
+) -> impl IntoResponse {
+    if !user.perms.contains(&GroupPermission::Admin)
+        && !user.perms.contains(&GroupPermission::Inspector)
+        && !user.perms.contains(&GroupPermission::Department)
+    {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
+            .into_response();
+    }
+
+    let mut activities = vec![];
+    for activity in roster.activities.unwrap_or_default() {
+        let activity = ObjectId::from_str(&activity);
+        if activity.is_err() {
+            return create_error(StatusCode::BAD_REQUEST, "Invalid activity ID".to_string())
+                .into_response();
+        }
+        activities.push(activity.unwrap());
+    }
+    let range = export_range(&roster.options);
+    if activities.is_empty() && range.from.is_none() && range.to.is_none() {
+        return create_error(
+            StatusCode::BAD_REQUEST,
+            "Select activities or a date range".to_string(),
+        )
+        .into_response();
+    }
+
+    if let Err(e) = ObjectId::from_str(&user.id) {
+        return create_error(StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e))
+            .into_response();
+    }
+    let user_id = ObjectId::from_str(&user.id).unwrap();
+
+    info!("Starting to export rosters by user {}", user_id);
+
+    let kind = ExportKind::Roster { activities };
+    start_task(db, config, io, user_id, kind, roster.options).await
+}
+
+/// Exports the activity times of the classes the user belongs to, so
+/// secretaries can report on their own class.
+pub async fn export_class_activity_times(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    Extension(io): Extension<SocketIo>,
+    user: UserData,
+    Json(options): Json<ExportActivityTimesOptions>,
+) -> impl IntoResponse {
+    if !user.perms.contains(&GroupPermission::Admin)
+        && !user.perms.contains(&GroupPermission::Secretary)
+    {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
+            .into_response();
+    }
 
     if let Err(e) = ObjectId::from_str(&user.id) {
         return create_error(StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e))
@@ -47,40 +172,156 @@ pub async fn export_activity_times(
     }
     let user_id = ObjectId::from_str(&user.id).unwrap();
 
-    println!("Starting to export excel by user {}", user_id);
+    let database = db.lock().await.clone();
+    let classes = find_user_classes(&database, user_id).await;
+    if let Err(e) = classes {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to validate user: {}", e),
+        )
+        .into_response();
+    }
+    let classes: Vec<ObjectId> = classes.unwrap().iter().map(|class| class._id).collect();
+    if classes.is_empty() {
+        return create_error(StatusCode::FORBIDDEN, "User has no class".to_string())
+            .into_response();
+    }
 
-    let mut tasks = exporters.lock().await;
-    let task = create_task(user_id, &options);
-    tasks.insert(task_id, task);
-    // Release the lock
-    drop(tasks);
+    info!(
+        "Starting to export class activity times by user {}",
+        user_id
+    );
 
-    let _ = spawn_task(task_id, Arc::clone(&exporters), db).await;
+    let kind = ExportKind::Class { classes };
+    start_task(db, config, io, user_id, kind, options).await
+}
+
+/// Saves a new task and starts it in the background, responding with its ID.
+async fn start_task(
+    db: Arc<Mutex<Database>>,
+    config: Arc<Config>,
+    io: SocketIo,
+    user_id: ObjectId,
+    kind: ExportKind,
+    options: ExportActivityTimesOptions,
+) -> Response {
+    let task_id = Uuid::new_v4().to_string();
+    info!("Received export task, job ID: {}", task_id);
+
+    let task = create_task(task_id.clone(), user_id, kind, &options);
+    if let Err(e) = enqueue_task(db, config, io, task).await {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+
+    (axum::http::StatusCode::OK, Json(task_id)).into_response()
+}
 
-    (axum::http::StatusCode::OK, Json(task_id.to_string())).into_response()
+/// Saves `task` and runs it in the background.
+async fn enqueue_task(
+    db: Arc<Mutex<Database>>,
+    config: Arc<Config>,
+    io: SocketIo,
+    task: Task,
+) -> Result<JoinHandle<()>, String> {
+    let database = db.lock().await.clone();
+    tasks::insert_task(&database, &task).await?;
+    Ok(spawn_task(
+        task._id,
+        task.kind,
+        task.options,
+        db,
+        config,
+        io,
+    ))
+}
+
+/// Finds a task only the user who started it, or an admin, may access.
+async fn find_own_task(db: &Database, user: &UserData, task_id: &str) -> Result<Task, Response> {
+    if Uuid::parse_str(task_id).is_err() {
+        return Err(
+            create_error(StatusCode::BAD_REQUEST, "Invalid task ID".to_string()).into_response(),
+        );
+    }
+    let task = tasks::find_task(db, task_id).await;
+    if let Err(e) = task {
+        return Err(create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response());
+    }
+    let task = task.unwrap();
+    if task.is_none() {
+        return Err(
+            create_error(StatusCode::NOT_FOUND, "Task not found".to_string()).into_response(),
+        );
+    }
+    let task = task.unwrap();
+    if task.actioner.to_hex() != user.id && !user.perms.contains(&GroupPermission::Admin) {
+        return Err(
+            create_error(StatusCode::FORBIDDEN, "Permission denied".to_string()).into_response(),
+        );
+    }
+    Ok(task)
 }
 
 pub async fn query_export_status(
-    Extension(exporters): Extension<Arc<ExportState>>,
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
     Path(task_id): Path<String>,
 ) -> impl IntoResponse {
-    let task_id = Uuid::parse_str(&task_id);
-    if let Err(_) = task_id {
-        return create_error(StatusCode::BAD_REQUEST, "Invalid task ID".to_string())
-            .into_response();
+    let db = db.lock().await.clone();
+    let task = find_own_task(&db, &user, &task_id).await;
+    if let Err(response) = task {
+        return response;
     }
-    let task_id = task_id.unwrap();
-    let tasks = exporters.lock().await;
-    let task = tasks.get(&task_id);
-    if let None = task {
-        return create_error(StatusCode::NOT_FOUND, "Task not found".to_string()).into_response();
+    (axum::http::StatusCode::OK, Json(task.unwrap())).into_response()
+}
+
+/// Streams a finished export file.
+pub async fn download_export(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Path(task_id): Path<String>,
+) -> impl IntoResponse {
+    let db = db.lock().await.clone();
+    let task = find_own_task(&db, &user, &task_id).await;
+    if let Err(response) = task {
+        return response;
     }
     let task = task.unwrap();
-    (axum::http::StatusCode::OK, Json(task)).into_response()
+    if task.status != TaskStatus::Done {
+        return create_error(StatusCode::CONFLICT, "Export is not finished".to_string())
+            .into_response();
+    }
+    let format = task.options.format;
+    let file = tokio::fs::File::open(tasks::export_file(&task._id, &format)).await;
+    if file.is_err() {
+        return create_error(StatusCode::NOT_FOUND, "Export file not found".to_string())
+            .into_response();
+    }
+    let disposition = format!(
+        "attachment; filename=\"{}-{}.{}\"",
+        task.kind.file_name(),
+        task._id,
+        format.extension()
+    );
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(ReaderStream::new(file.unwrap())),
+    )
+        .into_response()
 }
 
-fn create_task(user_id: ObjectId, options: &ExportActivityTimesOptions) -> Task {
+fn create_task(
+    task_id: String,
+    user_id: ObjectId,
+    kind: ExportKind,
+    options: &ExportActivityTimesOptions,
+) -> Task {
     Task {
+        _id: task_id,
+        kind,
         time: SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -89,79 +330,142 @@ fn create_task(user_id: ObjectId, options: &ExportActivityTimesOptions) -> Task
         options: options.clone(),
         actioner: user_id,
         result: None,
+        content_type: None,
         percent: Some(0.0),
+        schedule: None,
     }
 }
 
-async fn spawn_task(task_id: Uuid, exporters: Arc<ExportState>, db: Arc<Mutex<Database>>) {
-    println!("Start to spawn task {}", task_id);
-    let _ = tokio::spawn(async move {
-        process_task(task_id, Arc::clone(&exporters), Arc::clone(&db)).await;
+/// Run the export in the background so the request returns immediately.
+fn spawn_task(
+    task_id: String,
+    kind: ExportKind,
+    options: ExportActivityTimesOptions,
+    db: Arc<Mutex<Database>>,
+    config: Arc<Config>,
+    io: SocketIo,
+) -> JoinHandle<()> {
+    info!("Start to spawn task {}", task_id);
+    tokio::spawn(async move {
+        process_task(task_id, kind, options, db, config, io).await;
     })
-    .await;
 }
 
-async fn process_task(task_id: Uuid, exporters: Arc<ExportState>, db: Arc<Mutex<Database>>) {
-    println!("Start to process task {}", task_id);
-    let mut tasks = exporters.lock().await;
-    let task = tasks.get_mut(&task_id).unwrap();
-    task.status = TaskStatus::Processing;
-    println!("Task {} is processing", task_id);
-    let result = export_csv::export_to_dataframe(db).await;
-    if let Err(_) = result {
-        task.status = TaskStatus::Error;
-        task.result = None;
-        return;
+/// `start` and `end` are UNIX timestamps, where 0 leaves that side open.
+pub fn export_range(options: &ExportActivityTimesOptions) -> DateRange {
+    DateRange {
+        from: Some(options.start).filter(|start| *start > 0),
+        to: Some(options.end).filter(|end| *end > 0),
     }
-    let result = result.unwrap();
-    let temp_csv = NamedTempFile::new();
-    if let Err(_) = temp_csv {
-        task.status = TaskStatus::Error;
-        task.result = None;
+}
+
+/// Save `update` to the task and broadcast the new state to its room.
+async fn update_task(db: &Database, io: &SocketIo, task_id: &str, update: Document) {
+    let task = tasks::update_task(db, task_id, update).await;
+    if let Err(e) = task {
+        warn!("{}", e);
         return;
     }
-    let temp_csv = temp_csv.unwrap();
-    let temp_csv_name = temp_csv.path().to_str();
-    if let None = temp_csv_name {
-        task.status = TaskStatus::Error;
-        task.result = None;
-        return;
+    if let Some(task) = task.unwrap() {
+        let _ = io.to(task_id.to_string()).emit(EXPORT_PROGRESS_EVENT, task);
     }
-    let temp_csv_name = String::from(temp_csv_name.unwrap());
-    let temp_csv = temp_csv.as_file();
-    println!("Start to save to csv");
-    let result = export_csv::save_to_csv(result, temp_csv).await;
-    if let Err(_) = result {
-        task.status = TaskStatus::Error;
-        task.result = None;
+}
+
+async fn fail_task(db: &Database, io: &SocketIo, task_id: &str, error: String) {
+    warn!("Failed to export task {}: {}", task_id, error);
+    update_task(db, io, task_id, doc! {"status": "error", "result": null}).await;
+}
+
+async fn process_task(
+    task_id: String,
+    kind: ExportKind,
+    options: ExportActivityTimesOptions,
+    db: Arc<Mutex<Database>>,
+    config: Arc<Config>,
+    io: SocketIo,
+) {
+    info!("Start to process task {}", task_id);
+    let database = db.lock().await.clone();
+    update_task(&database, &io, &task_id, doc! {"status": "processing"}).await;
+    info!("Task {} is processing", task_id);
+
+    let (progress, mut receiver) = mpsc::unbounded_channel::<f64>();
+    let reporter = {
+        let database = database.clone();
+        let io = io.clone();
+        let task_id = task_id.clone();
+        tokio::spawn(async move {
+            let mut reported = 0.0;
+            while let Some(percent) = receiver.recv().await {
+                // Only broadcast whole percent steps
+                if percent.floor() <= reported {
+                    continue;
+                }
+                reported = percent.floor();
+                update_task(&database, &io, &task_id, doc! {"percent": reported}).await;
+            }
+        })
+    };
+
+    let format = options.format.clone();
+    let range = export_range(&options);
+    let timezone = config_timezone(&config);
+    let result = match &kind {
+        ExportKind::ActivityTimes => {
+            export_csv::export_to_dataframe(db, &[], &range, timezone, progress).await
+        }
+        ExportKind::Class { classes } => {
+            export_csv::export_to_dataframe(db, classes, &range, timezone, progress).await
+        }
+        ExportKind::Roster { activities } => {
+            // Rosters are a single query, so there is no progress to report
+            drop(progress);
+            export_roster::export_roster(db, activities, &range, timezone).await
+        }
+    };
+    let _ = reporter.await;
+    if let Err(e) = result {
+        fail_task(&database, &io, &task_id, e).await;
         return;
     }
-    println!("Start to convert to excel {}", temp_csv_name);
-    let temp_excel = NamedTempFile::new();
-    if let Err(_) = temp_excel {
-        task.status = TaskStatus::Error;
-        task.result = None;
+    let result = result.unwrap();
+    let temp_file = NamedTempFile::new();
+    if let Err(e) = temp_file {
+        fail_task(&database, &io, &task_id, e.to_string()).await;
         return;
     }
-    let temp_excel = temp_excel.unwrap();
-    let temp_excel_name = temp_excel.path().to_str();
-    if let None = temp_excel_name {
-        task.status = TaskStatus::Error;
-        task.result = None;
+    let temp_file = temp_file.unwrap();
+    info!("Start to save to {}", format.extension());
+    let result = match format {
+        ExportFormat::Csv => export_csv::save_to_csv(result, temp_file.as_file()).await,
+        ExportFormat::Json => export_json::save_to_json(result, temp_file.as_file()).await,
+        ExportFormat::Excel => {
+            export_excel::save_to_excel(result, kind.sheet_column(), temp_file.as_file()).await
+        }
+    };
+    if let Err(e) = result {
+        fail_task(&database, &io, &task_id, e).await;
         return;
     }
-    let temp_excel_name = String::from(temp_excel_name.unwrap());
-    let result = csv_to_excel::to_excel(temp_csv_name.clone(), temp_excel_name.clone());
-    if let Err(_) = result {
-        task.status = TaskStatus::Error;
-        task.result = None;
+    let target = tasks::export_file(&task_id, &format);
+    let copied = match fs::create_dir_all(tasks::EXPORT_DIRECTORY).await {
+        Ok(()) => fs::copy(temp_file.path(), &target).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = copied {
+        fail_task(&database, &io, &task_id, e.to_string()).await;
         return;
     }
-    task.status = TaskStatus::Done;
-    task.result = Some(temp_excel_name.clone());
-    fs::copy(
-        temp_excel_name.clone().as_str(),
-        format!("public/exports/{}.xlsx", task_id),
+    update_task(
+        &database,
+        &io,
+        &task_id,
+        doc! {
+            "status": "done",
+            "result": tasks::download_url(&task_id),
+            "contentType": format.content_type(),
+            "percent": 100.0,
+        },
     )
-    .unwrap();
+    .await;
 }
@@ -0,0 +1,306 @@
+use super::{create_task, enqueue_task};
+use crate::{
+    models::{
+        exports::ExportKind,
+        groups::GroupPermission,
+        response::create_error,
+        schedules::{ExportSchedule, ExportScheduleOptions},
+    },
+    utils::{
+        config::Config,
+        dates::config_timezone,
+        exports::{schedules, tasks},
+        groups::{classes::find_user_classes, permissions::find_user_permissions},
+        jwt::UserData,
+    },
+};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use bson::oid::ObjectId;
+use chrono::Utc;
+use mongodb::Database;
+use socketioxide::SocketIo;
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Checks a user with `perms` may run this kind of export and resolves what
+/// it covers, with the same rules as starting the export by hand.
+async fn schedule_kind(
+    db: &Database,
+    perms: &[GroupPermission],
+    user_id: ObjectId,
+    options: &ExportScheduleOptions,
+) -> Result<ExportKind, (StatusCode, String)> {
+    let allowed = |allowed: &[GroupPermission]| allowed.iter().any(|perm| perms.contains(perm));
+    let forbidden = || (StatusCode::FORBIDDEN, "Permission denied".to_string());
+    match options.kind.as_str() {
+        "activity-times" => {
+            if !allowed(&[GroupPermission::Admin, GroupPermission::Inspector]) {
+                return Err(forbidden());
+            }
+            Ok(ExportKind::ActivityTimes)
+        }
+        "class" => {
+            if !allowed(&[GroupPermission::Admin, GroupPermission::Secretary]) {
+                return Err(forbidden());
+            }
+            let classes = find_user_classes(db, user_id).await;
+            if let Err(e) = classes {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to validate user: {}", e),
+                ));
+            }
+            let classes: Vec<ObjectId> = classes.unwrap().iter().map(|class| class._id).collect();
+            if classes.is_empty() {
+                return Err((StatusCode::FORBIDDEN, "User has no class".to_string()));
+            }
+            Ok(ExportKind::Class { classes })
+        }
+        "roster" => {
+            if !allowed(&[
+                GroupPermission::Admin,
+                GroupPermission::Inspector,
+                GroupPermission::Department,
+            ]) {
+                return Err(forbidden());
+            }
+            let mut activities = vec![];
+            for activity in options.activities.clone().unwrap_or_default() {
+                let activity = ObjectId::from_str(&activity);
+                if activity.is_err() {
+                    return Err((StatusCode::BAD_REQUEST, "Invalid activity ID".to_string()));
+                }
+                activities.push(activity.unwrap());
+            }
+            if activities.is_empty() && options.days.is_none() {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Select activities or a number of days".to_string(),
+                ));
+            }
+            Ok(ExportKind::Roster { activities })
+        }
+        _ => Err((StatusCode::BAD_REQUEST, "Unknown export type".to_string())),
+    }
+}
+
+/// Checks the owner may still run the schedule, e.g. has not lost the
+/// permission or changed class since creating it, and resolves what the run
+/// covers now.
+async fn recheck_schedule(db: &Database, schedule: &ExportSchedule) -> Result<ExportKind, String> {
+    let perms = find_user_permissions(db, schedule.owner).await?;
+    let (kind, activities) = match &schedule.kind {
+        ExportKind::ActivityTimes => ("activity-times", None),
+        ExportKind::Class { .. } => ("class", None),
+        ExportKind::Roster { activities } => (
+            "roster",
+            Some(
+                activities
+                    .iter()
+                    .map(|activity| activity.to_hex())
+                    .collect(),
+            ),
+        ),
+    };
+    let options = ExportScheduleOptions {
+        cron: schedule.cron.clone(),
+        kind: kind.to_string(),
+        activities,
+        format: schedule.format.clone(),
+        days: schedule.days,
+        keep: Some(schedule.keep),
+    };
+    let kind = schedule_kind(db, &perms, schedule.owner, &options).await;
+    if let Err((_, e)) = kind {
+        return Err(e);
+    }
+    Ok(kind.unwrap())
+}
+
+/// Creates a schedule that repeats an export, e.g. every Monday morning.
+pub async fn create_export_schedule(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Json(options): Json<ExportScheduleOptions>,
+) -> impl IntoResponse {
+    let user_id = ObjectId::from_str(&user.id);
+    if let Err(e) = user_id {
+        return create_error(StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e))
+            .into_response();
+    }
+    let user_id = user_id.unwrap();
+    let keep = options.keep.unwrap_or(schedules::DEFAULT_KEEP);
+    if keep == 0 || keep > schedules::MAX_KEEP {
+        return create_error(
+            StatusCode::BAD_REQUEST,
+            format!("Keep between 1 and {} exports", schedules::MAX_KEEP),
+        )
+        .into_response();
+    }
+    let now = Utc::now().timestamp() as u64;
+    let next_run = schedules::next_run(&options.cron, now, config_timezone(&config));
+    if let Err(e) = next_run {
+        return create_error(StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let db = db.lock().await.clone();
+    let kind = schedule_kind(&db, &user.perms, user_id, &options).await;
+    if let Err((code, e)) = kind {
+        return create_error(code, e).into_response();
+    }
+    let schedule = ExportSchedule {
+        _id: ObjectId::new(),
+        owner: user_id,
+        cron: options.cron.trim().to_string(),
+        kind: kind.unwrap(),
+        format: options.format,
+        days: options.days,
+        keep,
+        next_run: next_run.unwrap(),
+        last_run: None,
+    };
+    if let Err(e) = schedules::insert_schedule(&db, &schedule).await {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    (StatusCode::OK, Json(schedule)).into_response()
+}
+
+/// Lists the user's schedules, or every schedule for admins.
+pub async fn read_export_schedules(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+) -> impl IntoResponse {
+    let owner = if user.perms.contains(&GroupPermission::Admin) {
+        None
+    } else {
+        let user_id = ObjectId::from_str(&user.id);
+        if let Err(e) = user_id {
+            return create_error(StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e))
+                .into_response();
+        }
+        Some(user_id.unwrap())
+    };
+    let db = db.lock().await.clone();
+    let schedules = schedules::find_schedules(&db, owner).await;
+    if let Err(e) = schedules {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    (StatusCode::OK, Json(schedules.unwrap())).into_response()
+}
+
+/// Stops a schedule and deletes the exports it kept.
+pub async fn remove_export_schedule(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Path(schedule_id): Path<String>,
+) -> impl IntoResponse {
+    let schedule_id = ObjectId::from_str(&schedule_id);
+    if schedule_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid schedule ID".to_string())
+            .into_response();
+    }
+    let schedule_id = schedule_id.unwrap();
+    let db = db.lock().await.clone();
+    let schedule = schedules::find_schedule(&db, schedule_id).await;
+    if let Err(e) = schedule {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    let schedule = schedule.unwrap();
+    if schedule.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Schedule not found".to_string())
+            .into_response();
+    }
+    let schedule = schedule.unwrap();
+    if schedule.owner.to_hex() != user.id && !user.perms.contains(&GroupPermission::Admin) {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
+            .into_response();
+    }
+    if let Err(e) = schedules::delete_schedule(&db, schedule_id).await {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    (StatusCode::OK, Json(schedule_id.to_hex())).into_response()
+}
+
+/// Starts an export task for every schedule due at `now`, a UNIX timestamp,
+/// and returns how many were started.
+pub async fn run_due_schedules(
+    db: Arc<Mutex<Database>>,
+    config: Arc<Config>,
+    io: SocketIo,
+    now: u64,
+) -> Result<usize, String> {
+    let database = db.lock().await.clone();
+    let timezone = config_timezone(&config);
+    let mut started = 0;
+    for mut schedule in schedules::find_due_schedules(&database, now).await? {
+        let next_run = schedules::next_run(&schedule.cron, now, timezone);
+        if let Err(e) = next_run {
+            warn!("Skipping schedule {}: {}", schedule._id, e);
+            continue;
+        }
+        let next_run = next_run.unwrap();
+        // Move the schedule on before running, so a failing export is not
+        // retried every minute
+        if !schedules::advance_schedule(&database, &schedule, next_run, now).await? {
+            continue;
+        }
+        schedule.next_run = next_run;
+        schedule.last_run = Some(now);
+        // The owner may have lost the permission since, so this run is
+        // skipped and the next one checks again
+        let kind = recheck_schedule(&database, &schedule).await;
+        if let Err(e) = kind {
+            warn!("Skipping schedule {}: {}", schedule._id, e);
+            continue;
+        }
+        schedule.kind = kind.unwrap();
+
+        let task_id = Uuid::new_v4().to_string();
+        info!(
+            "Starting scheduled export {} for schedule {}",
+            task_id, schedule._id
+        );
+        let mut task = create_task(
+            task_id.clone(),
+            schedule.owner,
+            schedule.kind.clone(),
+            &schedule.options(now),
+        );
+        task.schedule = Some(schedule._id);
+        let handle = enqueue_task(db.clone(), config.clone(), io.clone(), task).await?;
+        let database = database.clone();
+        tokio::spawn(async move {
+            let _ = handle.await;
+            if let Err(e) = finish_scheduled_task(&database, &schedule, &task_id).await {
+                warn!("{}", e);
+            }
+        });
+        started += 1;
+    }
+    Ok(started)
+}
+
+/// Tells the owner how a scheduled run went and drops exports beyond the
+/// number the schedule keeps.
+async fn finish_scheduled_task(
+    db: &Database,
+    schedule: &ExportSchedule,
+    task_id: &str,
+) -> Result<(), String> {
+    let task = tasks::find_task(db, task_id).await?;
+    if task.is_none() {
+        return Err(format!("Scheduled task {} disappeared", task_id));
+    }
+    let now = Utc::now().timestamp() as u64;
+    let notification = schedules::scheduled_task_notification(schedule, &task.unwrap(), now);
+    schedules::notify_owner(db, &notification).await?;
+    schedules::prune_scheduled_tasks(db, schedule._id, schedule.keep).await?;
+    Ok(())
+}
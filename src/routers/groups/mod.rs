@@ -0,0 +1,154 @@
+use crate::{
+    models::{
+        groups::{Group, GroupPermission},
+        response::{create_error, MetadataSize, ResponseStatus, SuccessResponse},
+        users::User,
+    },
+    routers::users::time::calculate_activity_times,
+    utils::{
+        config::Config,
+        dates::{config_timezone, DateRange},
+        groups::classes::find_user_classes,
+        jwt::UserData,
+        requirements::{calculate_progress, find_requirement_profile, RequirementProgress},
+    },
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadClassProgressQuery {
+    /// Only list students who have not met every requirement.
+    pub behind: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StudentProgress {
+    pub user: String,
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub progress: RequirementProgress,
+}
+
+pub async fn read_class_progress(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path(class_id): Path<String>,
+    Query(query): Query<ReadClassProgressQuery>,
+) -> impl IntoResponse {
+    let class_id = ObjectId::from_str(&class_id);
+    if class_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid class ID".to_string());
+    }
+    let class_id = class_id.unwrap();
+    let user_id = ObjectId::from_str(&user.id);
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let db = db.lock().await;
+    if user.perms.contains(&GroupPermission::Admin)
+        || user.perms.contains(&GroupPermission::Department)
+        || user.perms.contains(&GroupPermission::Auditor)
+        || user.perms.contains(&GroupPermission::Inspector)
+    {
+    } else if user.perms.contains(&GroupPermission::Secretary) {
+        let classes = find_user_classes(&db, user_id).await;
+        if let Err(e) = classes {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to validate user: {}", e),
+            );
+        }
+        if !classes.unwrap().iter().any(|class| class._id == class_id) {
+            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        }
+    } else {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let groups_collection: Collection<Group> = db.collection("groups");
+    let class = groups_collection
+        .find_one(doc! {"_id": class_id, "type": "class"}, None)
+        .await;
+    if class.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find class".to_string(),
+        );
+    }
+    let class = class.unwrap();
+    if class.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "Class not found".to_string());
+    }
+    let class = class.unwrap();
+    let users_collection: Collection<User> = db.collection("users");
+    let students = users_collection.find(doc! {"group": class_id}, None).await;
+    if students.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find class members".to_string(),
+        );
+    }
+    let students: Result<Vec<User>, _> = students.unwrap().try_collect().await;
+    if students.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find class members".to_string(),
+        );
+    }
+    let students = students.unwrap();
+    let ids: Vec<ObjectId> = students.iter().map(|student| student._id).collect();
+    let times =
+        calculate_activity_times(&db, &ids, &DateRange::default(), config_timezone(&config)).await;
+    if let Err(e) = times {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e);
+    }
+    let mut times = times.unwrap();
+    let profiles = config.requirements.clone().unwrap_or_default();
+    // Every student shares the class, so they share the profile
+    let profile = find_requirement_profile(&profiles, std::slice::from_ref(&class));
+    let mut progress: Vec<StudentProgress> = students
+        .into_iter()
+        .map(|student| StudentProgress {
+            progress: calculate_progress(
+                profile,
+                &times.remove(&student._id).unwrap_or_default().effective,
+            ),
+            user: student._id.to_hex(),
+            id: student.id,
+            name: student.name,
+        })
+        .filter(|student| !query.behind.unwrap_or(false) || !student.progress.eligible)
+        .collect();
+    // Students furthest behind first
+    progress.sort_by(|a, b| {
+        let shortfall = |student: &StudentProgress| {
+            student.progress.on_campus.shortfall
+                + student.progress.off_campus.shortfall
+                + student.progress.social_practice.shortfall
+        };
+        shortfall(b)
+            .total_cmp(&shortfall(a))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let size = progress.len() as u64;
+    let response: SuccessResponse<Vec<StudentProgress>, MetadataSize> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: progress,
+        metadata: Some(MetadataSize { size }),
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
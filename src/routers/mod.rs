@@ -1,4 +1,8 @@
 pub mod activities;
 pub mod auth;
+pub mod certificates;
 pub mod exports;
+pub mod groups;
+pub mod reviews;
+pub mod storage;
 pub mod users;
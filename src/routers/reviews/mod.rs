@@ -0,0 +1,357 @@
+use crate::{
+    models::{
+        activities::{Activity, ActivityType},
+        attendances::{ActivityMember, AttendanceAction, AttendanceStatus},
+        groups::GroupPermission,
+        response::{create_error, MetadataSize, ResponseStatus, SuccessResponse},
+        utils::datetime_or_u64,
+    },
+    routers::activities::members::{
+        history::{create_history, record_history},
+        images::{sign_images, SignedImage},
+    },
+    utils::{
+        attendances::transition::{transition_roles, validate_transition},
+        config::Config,
+        dates::{config_timezone, DateRange},
+        jwt::UserData,
+    },
+};
+use axum::{
+    extract::{Extension, Json, Query},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use bson::{doc, from_document, oid::ObjectId, Bson, Document};
+use chrono::FixedOffset;
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadReviewQuery {
+    pub page: Option<u32>,
+    pub perpage: Option<u32>,
+    pub class: Option<String>,
+    #[serde(rename = "type")]
+    pub activity_type: Option<ActivityType>,
+    /// Earliest activity date, as a UNIX timestamp.
+    pub from: Option<u64>,
+    /// Latest activity date, as a UNIX timestamp.
+    pub to: Option<u64>,
+}
+
+/// A pending member together with what an auditor needs to judge them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReviewItem {
+    pub activity: ObjectId,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub activity_type: ActivityType,
+    #[serde(deserialize_with = "datetime_or_u64")]
+    pub date: u64,
+    pub member: ActivityMember,
+    pub student: Option<String>,
+    pub classes: Vec<String>,
+    /// Signed URLs of the member's images.
+    #[serde(default)]
+    pub images: Vec<SignedImage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ReviewDecision {
+    pub activity: String,
+    pub member: String,
+    /// Overrides the shared reason for this member.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ReviewMembers {
+    pub status: AttendanceStatus,
+    pub reason: Option<String>,
+    pub members: Vec<ReviewDecision>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewResult {
+    Updated,
+    NotFound,
+    NotPending,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ReviewReport {
+    pub activity: String,
+    pub member: String,
+    pub result: ReviewResult,
+}
+
+fn is_reviewer(user: &UserData) -> bool {
+    user.perms.contains(&GroupPermission::Auditor) || user.perms.contains(&GroupPermission::Admin)
+}
+
+/// Pending members matching the filters, oldest activity first, as a single
+/// document holding one page of `items` and the total `size`.
+pub fn review_pipeline(
+    class: Option<ObjectId>,
+    activity_type: Option<&ActivityType>,
+    range: &DateRange,
+    timezone: FixedOffset,
+    page: u64,
+    perpage: u64,
+) -> Vec<Document> {
+    let mut filter = doc! {"members.status": "pending"};
+    if let Some(activity_type) = activity_type {
+        filter.insert("type", bson::to_bson(activity_type).unwrap());
+    }
+    let mut pipeline = vec![doc! {"$match": filter}];
+    pipeline.extend(range.stages(timezone));
+    pipeline.extend([
+        doc! {"$unwind": "$members"},
+        doc! {"$match": {"members.status": "pending"}},
+        doc! {"$lookup": {
+            "from": "users",
+            "localField": "members._id",
+            "foreignField": "_id",
+            "as": "user",
+        }},
+        doc! {"$unwind": {"path": "$user", "preserveNullAndEmptyArrays": true}},
+        doc! {"$lookup": {
+            "from": "groups",
+            "let": {"groups": {"$ifNull": ["$user.group", []]}},
+            "pipeline": [
+                {"$match": {"$expr": {"$and": [
+                    {"$in": ["$_id", "$$groups"]},
+                    {"$eq": ["$type", "class"]},
+                ]}}},
+            ],
+            "as": "classes",
+        }},
+    ]);
+    if let Some(class) = class {
+        pipeline.push(doc! {"$match": {"classes._id": class}});
+    }
+    // Oldest activities first, so members are reviewed in the order they waited.
+    // Members of one activity share its _id, so they are ordered too, or pages
+    // could repeat or skip them.
+    pipeline.push(doc! {"$sort": {"_id": 1, "members._id": 1}});
+    pipeline.push(doc! {"$project": {
+        "_id": 0,
+        "activity": "$_id",
+        "name": 1,
+        "type": 1,
+        "date": 1,
+        "member": "$members",
+        "student": "$user.name",
+        "classes": "$classes.name",
+    }});
+    pipeline.push(doc! {"$project": {"member.history": 0}});
+    pipeline.push(doc! {"$facet": {
+        "items": [
+            {"$skip": ((page - 1) * perpage) as i64},
+            {"$limit": perpage as i64},
+        ],
+        "size": [{"$count": "size"}],
+    }});
+    pipeline
+}
+
+pub async fn read_review_queue(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Query(query): Query<ReadReviewQuery>,
+) -> impl IntoResponse {
+    if !is_reviewer(&user) {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let page = query.page.unwrap_or(1).max(1) as u64;
+    // MongoDB rejects a limit of zero
+    let perpage = query.perpage.unwrap_or(20).max(1) as u64;
+    let class = query
+        .class
+        .map(|class| ObjectId::from_str(&class))
+        .transpose();
+    if class.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid class ID".to_string());
+    }
+    let class = class.unwrap();
+    let range = DateRange {
+        from: query.from,
+        to: query.to,
+    };
+    let pipeline = review_pipeline(
+        class,
+        query.activity_type.as_ref(),
+        &range,
+        config_timezone(&config),
+        page,
+        perpage,
+    );
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let cursor = collection.aggregate(pipeline, None).await;
+    if let Err(e) = cursor {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read review queue: {}", e),
+        );
+    }
+    let result = cursor.unwrap().try_next().await;
+    if let Err(e) = result {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read review queue: {}", e),
+        );
+    }
+    let result = result.unwrap().unwrap_or_default();
+    let size = result
+        .get_array("size")
+        .ok()
+        .and_then(|size| size.first())
+        .and_then(|size| size.as_document())
+        .and_then(|size| size.get("size"))
+        .and_then(|size| match size {
+            Bson::Int32(size) => Some(*size as u64),
+            Bson::Int64(size) => Some(*size as u64),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let mut items = vec![];
+    for document in result.get_array("items").cloned().unwrap_or_default() {
+        let document = document.as_document().cloned().unwrap_or_default();
+        let item = from_document::<ReviewItem>(document);
+        if let Err(e) = item {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read review queue: {}", e),
+            );
+        }
+        let mut item = item.unwrap();
+        // Storage keys are private, so hand out signed URLs instead
+        item.images = sign_images(item.member.images.take().unwrap_or_default());
+        items.push(item);
+    }
+    let response: SuccessResponse<Vec<ReviewItem>, MetadataSize> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: items,
+        metadata: Some(MetadataSize { size }),
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
+
+pub async fn review_members(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    user: UserData,
+    Json(request): Json<ReviewMembers>,
+) -> impl IntoResponse {
+    if !is_reviewer(&user) {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    if request.status != AttendanceStatus::Effective && request.status != AttendanceStatus::Refused
+    {
+        return create_error(
+            StatusCode::BAD_REQUEST,
+            "Members can only be approved or refused".to_string(),
+        );
+    }
+    let user_id = ObjectId::from_str(user.id.as_str());
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let roles = transition_roles(false, &user.perms);
+    let status = bson::to_bson(&request.status).unwrap();
+    let db = db.lock().await;
+    let collection: Collection<Activity> = db.collection("activities");
+    let mut reports = vec![];
+    for decision in request.members {
+        let report = |result| ReviewReport {
+            activity: decision.activity.clone(),
+            member: decision.member.clone(),
+            result,
+        };
+        let activity_id = ObjectId::from_str(&decision.activity);
+        let member_id = ObjectId::from_str(&decision.member);
+        if activity_id.is_err() || member_id.is_err() {
+            reports.push(report(ReviewResult::Invalid));
+            continue;
+        }
+        let activity_id = activity_id.unwrap();
+        let member_id = member_id.unwrap();
+        let activity = collection
+            .find_one(doc! {"_id": activity_id, "members._id": member_id}, None)
+            .await;
+        if activity.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to find activity".to_string(),
+            );
+        }
+        let member = activity.unwrap().and_then(|activity| {
+            activity
+                .members
+                .unwrap_or_default()
+                .into_iter()
+                .find(|member| member._id == member_id)
+        });
+        if member.is_none() {
+            reports.push(report(ReviewResult::NotFound));
+            continue;
+        }
+        let member = member.unwrap();
+        if validate_transition(&member.status, &request.status, &roles).is_err() {
+            reports.push(report(ReviewResult::NotPending));
+            continue;
+        }
+        let mut updated = member.clone();
+        updated.status = request.status.clone();
+        let mut entry = create_history(&updated, user_id, AttendanceAction::Status);
+        entry.reason = decision.reason.clone().or(request.reason.clone());
+        let set = doc! {"members.$.status": status.clone()};
+        let changes = record_history(set, &member, &entry);
+        if changes.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to serialize member history".to_string(),
+            );
+        }
+        // Only update members that are still pending, in case of a concurrent review
+        let result = collection
+            .update_one(
+                doc! {
+                    "_id": activity_id,
+                    "members": {"$elemMatch": {"_id": member_id, "status": "pending"}},
+                },
+                changes.unwrap(),
+                None,
+            )
+            .await;
+        if result.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update member status".to_string(),
+            );
+        }
+        if result.unwrap().modified_count == 1 {
+            reports.push(report(ReviewResult::Updated));
+        } else {
+            reports.push(report(ReviewResult::NotPending));
+        }
+    }
+    let response: SuccessResponse<Vec<ReviewReport>, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: reports,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
@@ -0,0 +1,62 @@
+use crate::{
+    models::response::create_error,
+    utils::storage::{
+        signature::{read_signing_key, verify_object_key},
+        Storage, StorageError,
+    },
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SignedObjectQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Serves a stored object to anyone holding a valid, unexpired signed URL.
+pub async fn read_object(
+    Extension(storage): Extension<Arc<dyn Storage>>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedObjectQuery>,
+) -> Response {
+    let secret = read_signing_key();
+    if let Err(e) = verify_object_key(
+        secret.as_bytes(),
+        &key,
+        query.expires,
+        &query.signature,
+        Utc::now().timestamp(),
+    ) {
+        return create_error(StatusCode::FORBIDDEN, e).into_response();
+    }
+    let data = storage.get(&key).await;
+    if let Err(e) = data {
+        let code = match e {
+            StorageError::NotFound => StatusCode::NOT_FOUND,
+            StorageError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        return create_error(code, e.to_string()).into_response();
+    }
+    let content_type = match key.rsplit('.').next() {
+        Some("webp") => "image/webp",
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        _ => "application/octet-stream",
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        data.unwrap(),
+    )
+        .into_response()
+}
@@ -2,10 +2,14 @@ use crate::{
     models::{
         activities::Activity,
         groups::GroupPermission,
-        response::{create_error, MetadataSize, ResponseStatus, SuccessResponse},
+        response::{create_error, MetadataPage, ResponseStatus, SuccessResponse},
     },
     routers::activities::read::ReadActivityQuery,
-    utils::{groups::same_class::validate_same_class, jwt::UserData},
+    utils::{
+        cursor::{decode_cursor, encode_cursor},
+        groups::same_class::validate_same_class,
+        jwt::UserData,
+    },
 };
 use axum::{
     extract::{Extension, Path, Query},
@@ -25,21 +29,27 @@ pub async fn read_user_activities(
         page,
         perpage,
         query,
+        cursor,
     }): Query<ReadActivityQuery>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
-    let page = page.unwrap_or(1);
-    let perpage = perpage.unwrap_or(10);
+    let page = page.unwrap_or(1).max(1);
+    let perpage = perpage.unwrap_or(10).max(1);
     let query = query.unwrap_or("".to_string());
+    let cursor = cursor.map(|cursor| decode_cursor(&cursor)).transpose();
+    if let Err(e) = cursor {
+        return create_error(StatusCode::BAD_REQUEST, e);
+    }
+    let cursor = cursor.unwrap();
     let user_id = ObjectId::from_str(&user_id);
-    if let Err(_) = user_id {
+    if user_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
     }
     let user_id = user_id.unwrap();
     let is_same_class = validate_same_class(
         db.clone(),
         ObjectId::from_str(user.id.as_str()).unwrap(),
-        user_id.clone(),
+        user_id,
     )
     .await;
     let db = db.lock().await;
@@ -53,85 +63,50 @@ pub async fn read_user_activities(
         } else {
             return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
         }
-    } else if user.id != user_id.clone().to_hex() {
+    } else if user.id != user_id.to_hex() {
         return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
     }
-    let counts = collection
-        .count_documents(
-            doc! {
-                "_id": user_id
-            },
-            None,
-        )
-        .await;
-    if let Err(_) = counts {
+    let filter = doc! {
+        "members._id": user_id,
+        "name": {"$regex": &query, "$options": "i"},
+    };
+    let counts = collection.count_documents(filter.clone(), None).await;
+    if counts.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to count documents".to_string(),
         );
     }
     let counts = counts.unwrap();
+    let mut filter = filter;
+    // With a cursor, continue after the last seen `_id` instead of skipping pages
+    if let Some(cursor) = cursor {
+        filter.insert("_id", doc! {"$lt": cursor});
+    }
+    let skip = if cursor.is_some() {
+        0
+    } else {
+        (page - 1) * perpage
+    };
     let pipeline = [
-        doc! {
-            "$match": {
-                "user": user_id
-            }
-        },
-        doc! {
-            "$match": {
-                "$or": [
-                    {
-                        "name": {
-                            "$regex": &query,
-                            "$options": "i"
-                        }
-                    },
-                ]
-            }
-        },
-        doc! {
-            "$project": {
-                // Only display `members._id == user_id` for `members` array
-                "members": {
-                    "$filter": {
-                        "input": "$members",
-                        "as": "member",
-                        "cond": {
-                            "$eq": [
-                                "$$member._id",
-                                user_id
-                            ]
-                        }
-                    }
-                },
-            }
-        },
-        doc! {
-            "$project": {
-                "members": 1,
-                "name": 1,
-                "description": 1,
-                "start": 1,
-                "end": 1,
-                "created_at": 1,
-                "updated_at": 1,
-                "deleted_at": 1,
-            }
-        },
-        doc! {
-            "$sort": {
-                "_id": -1
-            }
-        },
-        doc! {
-            "$skip": (page - 1) * perpage
-        },
-        doc! {
-            "$limit": perpage
-        },
+        doc! {"$match": filter},
+        doc! {"$sort": {"_id": -1}},
+        doc! {"$skip": skip},
+        doc! {"$limit": perpage},
+        // Only show the user among the members
+        doc! {"$set": {
+            "members": {
+                "$filter": {
+                    "input": "$members",
+                    "as": "member",
+                    "cond": {"$eq": ["$$member._id", user_id]}
+                }
+            },
+        }},
+        doc! {"$project": {"removedMembers": 0}},
     ];
     let cursor = collection.aggregate(pipeline, None).await;
-    if let Err(_) = cursor {
+    if cursor.is_err() {
         return create_error(
             StatusCode::INTERNAL_SERVER_ERROR,
             "Failed to fetch documents".to_string(),
@@ -139,10 +114,38 @@ pub async fn read_user_activities(
     }
     let mut cursor = cursor.unwrap();
     let mut activities: Vec<Activity> = Vec::new();
-    let metadata = MetadataSize { size: counts };
-    while let Some(activity) = cursor.try_next().await.unwrap() {
-        activities.push(from_document(activity).unwrap());
+    loop {
+        let document = cursor.try_next().await;
+        if let Err(e) = document {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch documents: {}", e),
+            );
+        }
+        let document = document.unwrap();
+        if document.is_none() {
+            break;
+        }
+        let activity = from_document(document.unwrap());
+        if let Err(e) = activity {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read activity: {}", e),
+            );
+        }
+        activities.push(activity.unwrap());
     }
+    let next_cursor = if activities.len() == perpage as usize {
+        activities
+            .last()
+            .map(|activity| encode_cursor(&activity._id))
+    } else {
+        None
+    };
+    let metadata = MetadataPage {
+        size: counts,
+        next_cursor,
+    };
     let response = SuccessResponse {
         status: ResponseStatus::Success,
         code: 200,
@@ -0,0 +1,267 @@
+use crate::{
+    models::{certificates::Certificate, response::create_error, users::User},
+    routers::{
+        activities::members::read::can_read_member,
+        users::time::{calculate_activity_times, signed_duration},
+    },
+    utils::{
+        certificates::{
+            generate_code, hash_document, is_duplicate_key,
+            pdf::{render_certificate, CertificateActivity, CertificateContent},
+            CODE_ATTEMPTS,
+        },
+        config::Config,
+        dates::{config_timezone, DateRange, DateRangeQuery},
+        exports::export_roster::format_date,
+        groups::classes::find_user_classes,
+        jwt::UserData,
+    },
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bson::{doc, oid::ObjectId, Bson, Document};
+use chrono::{FixedOffset, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{Collection, Database};
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+/// Effective attendances of `user` within `range`, oldest first.
+async fn find_effective_activities(
+    db: &Database,
+    user: ObjectId,
+    range: &DateRange,
+    timezone: FixedOffset,
+) -> Result<Vec<CertificateActivity>, String> {
+    let collection: Collection<Document> = db.collection("activities");
+    let mut pipeline = vec![doc! {"$match": {"members._id": user}}];
+    pipeline.extend(range.stages(timezone));
+    pipeline.extend([
+        doc! {"$unwind": "$members"},
+        doc! {"$match": {"members._id": user, "members.status": "effective"}},
+        doc! {"$sort": {"date": 1, "_id": 1}},
+        doc! {"$project": {
+            "name": 1,
+            "date": 1,
+            "mode": "$members.mode",
+            "category": 1,
+            "duration": "$members.duration",
+        }},
+    ]);
+    let cursor = collection.aggregate(pipeline, None).await;
+    if cursor.is_err() {
+        return Err("Failed to aggregate documents".to_string());
+    }
+    let documents: Result<Vec<Document>, _> = cursor.unwrap().try_collect().await;
+    if documents.is_err() {
+        return Err("Failed to aggregate documents".to_string());
+    }
+    Ok(documents
+        .unwrap()
+        .iter()
+        .map(|document| CertificateActivity {
+            date: format_date(document.get("date").unwrap_or(&Bson::Null), timezone),
+            name: document.get_str("name").unwrap_or_default().to_string(),
+            mode: document.get_str("mode").unwrap_or_default().to_string(),
+            // Printed with the same sign as in the totals
+            duration: signed_duration(
+                document.get_str("category").unwrap_or_default(),
+                match document.get("duration") {
+                    Some(Bson::Double(duration)) => *duration,
+                    Some(Bson::Int32(duration)) => *duration as f64,
+                    Some(Bson::Int64(duration)) => *duration as f64,
+                    _ => 0.0,
+                },
+            ),
+        })
+        .collect())
+}
+
+fn describe_period(range: &DateRange, timezone: FixedOffset) -> String {
+    if range.from.is_none() && range.to.is_none() {
+        return "全部 All time".to_string();
+    }
+    let day = |time: Option<u64>| {
+        time.map(|time| format_date(&Bson::Int64(time as i64), timezone))
+            .unwrap_or_default()
+    };
+    format!("{} – {}", day(range.from), day(range.to))
+}
+
+/// Issues a PDF certificate of a user's effective hours. Each certificate is
+/// recorded with a verification code printed on every page.
+pub async fn issue_certificate(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path(user_id): Path<String>,
+    Query(query): Query<DateRangeQuery>,
+) -> Response {
+    let range = query.resolve(&config);
+    if let Err(e) = range {
+        return create_error(StatusCode::BAD_REQUEST, e).into_response();
+    }
+    let range = range.unwrap();
+    let user_id = ObjectId::from_str(&user_id);
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string())
+            .into_response();
+    }
+    let user_id = user_id.unwrap();
+    let issuer_id = ObjectId::from_str(&user.id);
+    if issuer_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string())
+            .into_response();
+    }
+    let issuer_id = issuer_id.unwrap();
+    let db = db.lock().await.clone();
+    let allowed = can_read_member(&db, &user, user_id).await;
+    if let Err(e) = allowed {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to validate user: {}", e),
+        )
+        .into_response();
+    }
+    if !allowed.unwrap() {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string())
+            .into_response();
+    }
+
+    let users: Collection<User> = db.collection("users");
+    let student = users.find_one(doc! {"_id": user_id}, None).await;
+    if student.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to find user".to_string(),
+        )
+        .into_response();
+    }
+    let student = student.unwrap();
+    if student.is_none() {
+        return create_error(StatusCode::NOT_FOUND, "User not found".to_string()).into_response();
+    }
+    let student = student.unwrap();
+    let classes = find_user_classes(&db, user_id).await;
+    if let Err(e) = classes {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    let class = classes
+        .unwrap()
+        .iter()
+        .map(|class| class.name.clone())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let timezone = config_timezone(&config);
+    let times = calculate_activity_times(&db, &[user_id], &range, timezone).await;
+    if let Err(e) = times {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    let time = times
+        .unwrap()
+        .remove(&user_id)
+        .unwrap_or_default()
+        .effective;
+    let activities = find_effective_activities(&db, user_id, &range, timezone).await;
+    if let Err(e) = activities {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+
+    let certificates_config = config.certificates.clone().unwrap_or_default();
+    let font = tokio::fs::read(&certificates_config.font).await;
+    if font.is_err() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Certificate font not found".to_string(),
+        )
+        .into_response();
+    }
+    let font = Arc::new(font.unwrap());
+
+    let now = Utc::now().timestamp() as u64;
+    let content = CertificateContent {
+        issuer: certificates_config.issuer,
+        name: student.name.clone(),
+        id: student.id.clone(),
+        class,
+        period: describe_period(&range, timezone),
+        issued: format_date(&Bson::Int64(now as i64), timezone),
+        code: String::new(),
+        time: time.clone(),
+        activities: activities.unwrap(),
+    };
+    let collection: Collection<Certificate> = db.collection("certificates");
+    let mut issued = None;
+    // The code is printed on the certificate, so a code that turns out to be
+    // taken means rendering it again
+    for _ in 0..CODE_ATTEMPTS {
+        let code = generate_code();
+        let mut content = content.clone();
+        content.code = code.clone();
+        // Embedding a CJK font takes a while, so keep it off the async workers
+        let font = font.clone();
+        let pdf = tokio::task::spawn_blocking(move || render_certificate(&font, &content)).await;
+        if pdf.is_err() {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render certificate".to_string(),
+            )
+            .into_response();
+        }
+        let pdf = pdf.unwrap();
+        if let Err(e) = pdf {
+            return create_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+        }
+        let pdf = pdf.unwrap();
+
+        let certificate = Certificate {
+            _id: ObjectId::new(),
+            code,
+            user: user_id,
+            name: student.name.clone(),
+            issued_at: now,
+            issued_by: issuer_id,
+            from: range.from,
+            to: range.to,
+            time: time.clone(),
+            hash: hash_document(&pdf),
+        };
+        match collection.insert_one(&certificate, None).await {
+            Ok(_) => {
+                issued = Some(pdf);
+                break;
+            }
+            Err(e) if is_duplicate_key(&e) => continue,
+            Err(_) => {
+                return create_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to record certificate".to_string(),
+                )
+                .into_response();
+            }
+        }
+    }
+    if issued.is_none() {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to generate a unique certificate code".to_string(),
+        )
+        .into_response();
+    }
+    let pdf = issued.unwrap();
+
+    let disposition = format!("attachment; filename=\"certificate-{}.pdf\"", student.id);
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        pdf,
+    )
+        .into_response()
+}
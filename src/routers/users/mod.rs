@@ -1,2 +1,4 @@
 pub mod activity;
+pub mod certificate;
+pub mod progress;
 pub mod time;
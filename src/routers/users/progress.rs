@@ -0,0 +1,74 @@
+use crate::{
+    models::response::{create_error, ResponseStatus, SuccessResponse},
+    routers::{activities::members::read::can_read_member, users::time::calculate_activity_times},
+    utils::{
+        config::Config,
+        dates::{config_timezone, DateRange},
+        groups::classes::find_user_classes,
+        jwt::UserData,
+        requirements::{calculate_progress, find_requirement_profile, RequirementProgress},
+    },
+};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use bson::oid::ObjectId;
+use mongodb::Database;
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+pub async fn read_user_progress(
+    Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
+    Path(user_id): Path<String>,
+) -> impl IntoResponse {
+    let user_id = ObjectId::from_str(&user_id);
+    if user_id.is_err() {
+        return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
+    }
+    let user_id = user_id.unwrap();
+    let db = db.lock().await;
+    let allowed = can_read_member(&db, &user, user_id).await;
+    if let Err(e) = allowed {
+        return create_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to validate user: {}", e),
+        );
+    }
+    if !allowed.unwrap() {
+        return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+    }
+    let classes = find_user_classes(&db, user_id).await;
+    if let Err(e) = classes {
+        return create_error(StatusCode::NOT_FOUND, e);
+    }
+    let classes = classes.unwrap();
+    let times = calculate_activity_times(
+        &db,
+        &[user_id],
+        &DateRange::default(),
+        config_timezone(&config),
+    )
+    .await;
+    if let Err(e) = times {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e);
+    }
+    let time = times
+        .unwrap()
+        .remove(&user_id)
+        .unwrap_or_default()
+        .effective;
+    let profiles = config.requirements.clone().unwrap_or_default();
+    let progress = calculate_progress(find_requirement_profile(&profiles, &classes), &time);
+    let response: SuccessResponse<RequirementProgress, ()> = SuccessResponse {
+        status: ResponseStatus::Success,
+        code: 200,
+        data: progress,
+        metadata: None,
+    };
+    let response = serde_json::to_string(&response).unwrap();
+    (StatusCode::OK, Json(response))
+}
@@ -1,115 +1,215 @@
-use crate::models::{
-    activities::Activity,
-    response::{create_error, ResponseStatus, SuccessResponse},
+use crate::{
+    models::{
+        activities::Activity,
+        groups::GroupPermission,
+        response::{create_error, ResponseStatus, SuccessResponse},
+    },
+    routers::activities::members::read::can_read_member,
+    utils::{
+        config::Config,
+        dates::{config_timezone, DateRange, DateRangeQuery},
+        jwt::UserData,
+    },
 };
 use axum::{
-    extract::{Extension, Path},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
-use bson::{doc, from_document, oid::ObjectId};
+use bson::{doc, oid::ObjectId, Bson, Document};
+use chrono::FixedOffset;
 use futures::stream::TryStreamExt;
 use mongodb::{Collection, Database};
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 use tokio::sync::Mutex;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// Hours from special activities by category. They are also counted in the
+/// mode they were credited in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct SpecialActivityTime {
+    pub prize: f64,
+    pub club: f64,
+    /// Hours imported from records kept before this system.
+    pub historical: f64,
+    /// Hours taken away, always zero or negative.
+    pub deduction: f64,
+    pub other: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct UserActivityTime {
     pub on_campus: f64,
     pub off_campus: f64,
     pub social_practice: f64,
     pub total: f64,
+    #[serde(default)]
+    pub special: SpecialActivityTime,
+}
+
+impl UserActivityTime {
+    /// Adds every category of `other` to this time, e.g. for class totals.
+    pub fn merge(&mut self, other: &UserActivityTime) {
+        self.on_campus += other.on_campus;
+        self.off_campus += other.off_campus;
+        self.social_practice += other.social_practice;
+        self.total += other.total;
+        self.special.prize += other.special.prize;
+        self.special.club += other.special.club;
+        self.special.historical += other.special.historical;
+        self.special.deduction += other.special.deduction;
+        self.special.other += other.special.other;
+    }
+}
+
+/// Activity time by member status. Drafts and rejected attendances are not
+/// counted anywhere.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct UserActivityTimes {
+    pub effective: UserActivityTime,
+    pub pending: UserActivityTime,
+    pub refused: UserActivityTime,
+}
+
+/// Deductions count against the member whatever sign they were stored with.
+pub fn signed_duration(category: &str, duration: f64) -> f64 {
+    if category == "deduction" {
+        -duration.abs()
+    } else {
+        duration
+    }
+}
+
+impl UserActivityTimes {
+    /// Credits `duration` hours of an attendance with the given member status,
+    /// mode and special activity category, all as stored in the database.
+    pub fn add(&mut self, status: &str, mode: &str, category: &str, duration: f64) {
+        let time = match status {
+            "effective" => &mut self.effective,
+            "pending" => &mut self.pending,
+            "refused" => &mut self.refused,
+            _ => return,
+        };
+        let duration = signed_duration(category, duration);
+        match mode {
+            "on-campus" => time.on_campus += duration,
+            "off-campus" => time.off_campus += duration,
+            "social-practice" => time.social_practice += duration,
+            _ => {}
+        }
+        match category {
+            "prize" => time.special.prize += duration,
+            "club" => time.special.club += duration,
+            "import" => time.special.historical += duration,
+            "deduction" => time.special.deduction += duration,
+            "other" => time.special.other += duration,
+            _ => {}
+        }
+        time.total += duration;
+    }
+}
+
+/// Sums the activity time of each of `users` within `range` in one
+/// aggregation. Users without any activity are left out of the result.
+pub async fn calculate_activity_times(
+    db: &Database,
+    users: &[ObjectId],
+    range: &DateRange,
+    timezone: FixedOffset,
+) -> Result<HashMap<ObjectId, UserActivityTimes>, String> {
+    let collection: Collection<Activity> = db.collection("activities");
+    let mut pipeline = vec![doc! {"$match": {"members._id": {"$in": users}}}];
+    pipeline.extend(range.stages(timezone));
+    pipeline.extend([
+        doc! {"$unwind": "$members"},
+        doc! {"$match": {
+            "members._id": {"$in": users},
+            "members.status": {"$in": ["effective", "pending", "refused"]},
+        }},
+        doc! {
+            "$group": {
+                "_id": {
+                    "user": "$members._id",
+                    "mode": "$members.mode",
+                    "status": "$members.status",
+                    "category": "$category",
+                },
+                "duration": { "$sum": "$members.duration" }
+            }
+        },
+    ]);
+    let cursor = collection.aggregate(pipeline, None).await;
+    if cursor.is_err() {
+        return Err("Failed to aggregate documents".to_string());
+    }
+    let documents: Result<Vec<Document>, _> = cursor.unwrap().try_collect().await;
+    if documents.is_err() {
+        return Err("Failed to aggregate documents".to_string());
+    }
+    let mut times: HashMap<ObjectId, UserActivityTimes> = HashMap::new();
+    for document in documents.unwrap() {
+        let group = document.get_document("_id");
+        if group.is_err() {
+            continue;
+        }
+        let group = group.unwrap();
+        let user = group.get_object_id("user");
+        if user.is_err() {
+            continue;
+        }
+        let duration = match document.get("duration") {
+            Some(Bson::Double(duration)) => *duration,
+            Some(Bson::Int32(duration)) => *duration as f64,
+            Some(Bson::Int64(duration)) => *duration as f64,
+            _ => 0.0,
+        };
+        times.entry(user.unwrap()).or_default().add(
+            group.get_str("status").unwrap_or_default(),
+            group.get_str("mode").unwrap_or_default(),
+            group.get_str("category").unwrap_or_default(),
+            duration,
+        );
+    }
+    Ok(times)
 }
 
 pub async fn calculate_user_activity_time(
     Extension(db): Extension<Arc<Mutex<Database>>>,
+    Extension(config): Extension<Arc<Config>>,
+    user: UserData,
     Path(user_id): Path<String>,
+    Query(query): Query<DateRangeQuery>,
 ) -> impl IntoResponse {
+    let range = query.resolve(&config);
+    if let Err(e) = range {
+        return create_error(StatusCode::BAD_REQUEST, e);
+    }
+    let range = range.unwrap();
     let db = db.lock().await;
     let user_id = ObjectId::from_str(&user_id);
-    if let Err(_) = user_id {
+    if user_id.is_err() {
         return create_error(StatusCode::BAD_REQUEST, "Invalid user ID".to_string());
     }
     let user_id = user_id.unwrap();
-    let collection: Collection<Activity> = db.collection("activities");
-    let pipeline = vec![
-        doc! {
-            "$match": {
-                "$or": [
-                    { "members._id": user_id },
-                    { "members._id": user_id.to_hex() }
-                ]
-            }
-        },
-        doc! {
-            "$unwind": "$members"
-        },
-        doc! {
-            "$match": {
-                "$or": [
-                    { "members._id": user_id },
-                    { "members._id": user_id.to_hex() }
-                ]
-            }
-        },
-        doc! {
-            "$group": {
-                "_id": "$members.mode",
-                "totalDuration": { "$sum": "$members.duration" }
-            }
-        },
-        doc! {
-            "$group": {
-                "_id": null,
-                "on_campus": {
-                    "$sum": {
-                        "$cond": [{ "$eq": ["$_id", "on-campus"] }, "$totalDuration", 0.0]
-                    }
-                },
-                "off_campus": {
-                    "$sum": {
-                        "$cond": [{ "$eq": ["$_id", "off-campus"] }, "$totalDuration", 0.0]
-                    }
-                },
-                "social_practice": {
-                    "$sum": {
-                        "$cond": [{ "$eq": ["$_id", "social-practice"] }, "$totalDuration", 0.0]
-                    }
-                },
-                "total": { "$sum": "$totalDuration" }
-            }
-        },
-        doc! {
-            "$project": {
-                "_id": 0,
-                "on_campus": 1,
-                "off_campus": 1,
-                "social_practice": 1,
-                "total": 1
-            }
-        },
-    ];
-    let cursor = collection.aggregate(pipeline, None);
-    let mut result = UserActivityTime {
-        on_campus: 0.0,
-        off_campus: 0.0,
-        social_practice: 0.0,
-        total: 0.0,
-    };
-    let cursor = cursor.await;
-    if let Err(_) = cursor {
-        return create_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to aggregate documents".to_string(),
-        );
+    // Inspectors export everyone's hours anyway
+    if !user.perms.contains(&GroupPermission::Inspector) {
+        let allowed = can_read_member(&db, &user, user_id).await;
+        if let Err(e) = allowed {
+            return create_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to validate user: {}", e),
+            );
+        }
+        if !allowed.unwrap() {
+            return create_error(StatusCode::FORBIDDEN, "Permission denied".to_string());
+        }
     }
-    // Only the first document is needed
-    let mut cursor = cursor.unwrap();
-    if let Some(doc) = cursor.try_next().await.unwrap() {
-        result = from_document(doc).unwrap();
+    let times = calculate_activity_times(&db, &[user_id], &range, config_timezone(&config)).await;
+    if let Err(e) = times {
+        return create_error(StatusCode::INTERNAL_SERVER_ERROR, e);
     }
-    let response: SuccessResponse<UserActivityTime, ()> = SuccessResponse {
+    let result = times.unwrap().remove(&user_id).unwrap_or_default();
+    let response: SuccessResponse<UserActivityTimes, ()> = SuccessResponse {
         status: ResponseStatus::Success,
         code: StatusCode::OK.as_u16(),
         data: result,
@@ -4,17 +4,25 @@ mod tests {
         database,
         models::{
             activities::{Activity, ActivityStatus, ActivityType, SpecialActivityCategory},
+            attendances::AttendanceStatus,
             groups::GroupPermission,
+            response::{MetadataPage, SuccessResponse},
         },
-        routers::activities::{self, read::ReadActivityQuery},
+        routers::{
+            activities::{self, read::ReadActivityQuery},
+            users::activity::read_user_activities,
+        },
+        tests::helpers,
         utils::jwt::{TokenType, UserData},
     };
     use axum::{
         extract::{Path, Query},
+        http::StatusCode,
         response::IntoResponse,
         Extension, Json,
     };
-    use bson::oid::ObjectId;
+    use bson::{doc, oid::ObjectId};
+    use mongodb::Collection;
     use std::{str::FromStr, sync::Arc, time::SystemTime};
     use tokio::sync::Mutex;
 
@@ -27,9 +35,7 @@ mod tests {
             term: TokenType::LongTerm,
         };
         let client = database::create_client().await;
-        if let Err(_) = client {
-            assert!(false);
-        }
+        assert!(client.is_ok());
         let client = client.unwrap();
         let extension = Arc::new(Mutex::new(client));
         let extension = Extension(extension);
@@ -40,6 +46,7 @@ mod tests {
                 page: Some(1),
                 perpage: Some(10),
                 query: Some("".to_string()),
+                cursor: None,
             }),
         )
         .await;
@@ -50,7 +57,7 @@ mod tests {
     async fn create_activity() {
         let activity_id = ObjectId::new();
         let activity = Activity {
-            _id: activity_id.clone(),
+            _id: activity_id,
             activity_type: ActivityType::Special,
             name: "测试".to_string(),
             description: Some(
@@ -71,6 +78,7 @@ mod tests {
                 .unwrap()
                 .as_secs(),
             members: Some(vec![]),
+            removed_members: None,
             location: Some("测试".to_string()),
             category: Some(SpecialActivityCategory::Other),
         };
@@ -82,9 +90,7 @@ mod tests {
             term: TokenType::LongTerm,
         };
         let client = database::create_client().await;
-        if let Err(_) = client {
-            assert!(false);
-        }
+        assert!(client.is_ok());
         let client = client.unwrap();
         let extension = Arc::new(Mutex::new(client));
         let extension = Extension(extension);
@@ -147,4 +153,47 @@ mod tests {
         let result = result.into_response();
         assert!(result.status().is_success());
     }
+    #[tokio::test]
+    async fn user_activities_only_show_the_user() {
+        let db = database::create_client().await.unwrap();
+        let student = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![
+                helpers::member(student, AttendanceStatus::Effective),
+                helpers::member(ObjectId::new(), AttendanceStatus::Effective),
+            ],
+        )
+        .await;
+        let result = read_user_activities(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            helpers::token(student, vec![GroupPermission::Student]),
+            Query(ReadActivityQuery {
+                // Pages count from 1, 0 must not underflow
+                page: Some(0),
+                perpage: Some(10),
+                query: None,
+                cursor: None,
+            }),
+            Path(student.to_hex()),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let response: SuccessResponse<Vec<Activity>, MetadataPage> =
+            helpers::read_response(result).await;
+        assert_eq!(response.metadata.unwrap().size, 1);
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0]._id, activity_id);
+        let members = response.data[0].members.clone().unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0]._id, student);
+    }
 }
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database, launch::create_certificate_index, models::certificates::Certificate,
+        routers::users::time::UserActivityTime, utils::certificates::is_duplicate_key,
+    };
+    use bson::{doc, oid::ObjectId};
+    use mongodb::Collection;
+
+    fn certificate(code: &str) -> Certificate {
+        Certificate {
+            _id: ObjectId::new(),
+            code: code.to_string(),
+            user: ObjectId::new(),
+            name: "测试".to_string(),
+            issued_at: 0,
+            issued_by: ObjectId::new(),
+            from: None,
+            to: None,
+            time: UserActivityTime::default(),
+            hash: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn certificate_codes_are_unique() {
+        let db = database::create_client().await.unwrap();
+        // Running it again on every start must not fail
+        create_certificate_index(&db).await.unwrap();
+        create_certificate_index(&db).await.unwrap();
+        let collection: Collection<Certificate> = db.collection("certificates");
+        // Lowercase letters never appear in generated codes
+        let code = format!("test-{}", ObjectId::new().to_hex());
+        collection
+            .insert_one(certificate(&code), None)
+            .await
+            .unwrap();
+        let duplicate = collection.insert_one(certificate(&code), None).await;
+        collection
+            .delete_many(doc! {"code": &code}, None)
+            .await
+            .unwrap();
+        assert!(is_duplicate_key(&duplicate.unwrap_err()));
+    }
+}
@@ -0,0 +1,437 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database,
+        models::{
+            activities::Activity,
+            attendances::{AttendanceAction, AttendanceMode, AttendanceStatus},
+            groups::GroupPermission,
+        },
+        routers::activities::members::{
+            history::read_member_history,
+            insert::{
+                insert_members_into_activity, InsertActivityMembers, InsertMemberReport,
+                InsertMemberResult,
+            },
+            remove::remove_member_from_activity,
+            update::{
+                update_member_impression, update_member_mode, update_member_status,
+                UpdateActivityMemberImpression, UpdateActivityMemberMode,
+                UpdateActivityMemberStatus,
+            },
+        },
+        tests::helpers,
+        utils::{
+            config::{load_or_init_config, DurationConfig, ImpressionConfig},
+            impressions::ImpressionIssue,
+        },
+    };
+    use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+    use bson::{doc, oid::ObjectId, Document};
+    use mongodb::{Collection, Database};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    async fn connect() -> Database {
+        database::create_client().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn students_cannot_insert_members_in_bulk() {
+        let db = connect().await;
+        let config = load_or_init_config().await.unwrap();
+        let result = insert_members_into_activity(
+            Extension(Arc::new(Mutex::new(db))),
+            Extension(Arc::new(config)),
+            helpers::token(ObjectId::new(), vec![GroupPermission::Student]),
+            Path(ObjectId::new().to_hex()),
+            Json(InsertActivityMembers {
+                members: Some(vec![ObjectId::new().to_hex()]),
+                group: None,
+                status: None,
+                mode: None,
+                duration: None,
+                override_rules: None,
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+    }
+    #[tokio::test]
+    async fn bulk_insert_reports_every_member() {
+        let db = connect().await;
+        let config = load_or_init_config().await.unwrap();
+        let users: Collection<Document> = db.collection("users");
+        let student = ObjectId::new();
+        users
+            .insert_one(
+                doc! {"_id": student, "id": "00000000", "name": "测试", "group": [], "password": ""},
+                None,
+            )
+            .await
+            .unwrap();
+        let enrolled = ObjectId::new();
+        let missing = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(enrolled, AttendanceStatus::Draft)],
+        )
+        .await;
+        let requested = vec![
+            student.to_hex(),
+            student.to_hex(),
+            enrolled.to_hex(),
+            missing.to_hex(),
+            "not-an-id".to_string(),
+        ];
+        let result = insert_members_into_activity(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            Extension(Arc::new(config)),
+            helpers::token(ObjectId::new(), vec![GroupPermission::Admin]),
+            Path(activity_id.to_hex()),
+            Json(InsertActivityMembers {
+                members: Some(requested),
+                group: None,
+                status: None,
+                mode: None,
+                duration: None,
+                override_rules: None,
+            }),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+        users.delete_one(doc! {"_id": student}, None).await.unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let reports: Vec<InsertMemberReport> = helpers::read_data(result).await;
+        let results: Vec<(String, InsertMemberResult)> = reports
+            .into_iter()
+            .map(|report| (report.member, report.result))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("not-an-id".to_string(), InsertMemberResult::Invalid),
+                (student.to_hex(), InsertMemberResult::Inserted),
+                (student.to_hex(), InsertMemberResult::Duplicated),
+                (enrolled.to_hex(), InsertMemberResult::Duplicated),
+                (missing.to_hex(), InsertMemberResult::NotFound),
+            ]
+        );
+        let members = activity.members.unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1]._id, student);
+    }
+    #[tokio::test]
+    async fn students_cannot_read_the_history_of_others() {
+        let db = connect().await;
+        let result = read_member_history(
+            Extension(Arc::new(Mutex::new(db))),
+            helpers::token(ObjectId::new(), vec![GroupPermission::Student]),
+            Path((ObjectId::new().to_hex(), ObjectId::new().to_hex())),
+        )
+        .await
+        .into_response();
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+    }
+    #[tokio::test]
+    async fn mode_changes_are_recorded_in_history() {
+        let db = connect().await;
+        let student = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(student, AttendanceStatus::Draft)],
+        )
+        .await;
+        let config = Extension(Arc::new(load_or_init_config().await.unwrap()));
+        let shared = Extension(Arc::new(Mutex::new(db.clone())));
+        let path = (activity_id.to_hex(), student.to_hex());
+        let updated = update_member_mode(
+            shared.clone(),
+            config.clone(),
+            helpers::token(student, vec![GroupPermission::Student]),
+            Path(path.clone()),
+            Json(UpdateActivityMemberMode {
+                mode: AttendanceMode::OffCampus,
+                override_rules: None,
+            }),
+        )
+        .await
+        .into_response();
+        let forbidden = update_member_mode(
+            shared.clone(),
+            config,
+            helpers::token(ObjectId::new(), vec![GroupPermission::Student]),
+            Path(path.clone()),
+            Json(UpdateActivityMemberMode {
+                mode: AttendanceMode::OnCampus,
+                override_rules: None,
+            }),
+        )
+        .await
+        .into_response();
+        let history = read_member_history(
+            shared,
+            helpers::token(student, vec![GroupPermission::Student]),
+            Path(path),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(updated.status(), StatusCode::OK);
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+        assert_eq!(history.status(), StatusCode::OK);
+        let history: Vec<crate::models::attendances::AttendanceHistory> =
+            helpers::read_data(history).await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].action, Some(AttendanceAction::Mode));
+        assert_eq!(history[0].mode, Some(AttendanceMode::OffCampus));
+        assert_eq!(history[0].actor, student);
+    }
+    #[tokio::test]
+    async fn mode_changes_recheck_the_duration() {
+        let db = connect().await;
+        let mut config = load_or_init_config().await.unwrap();
+        config.durations = Some(DurationConfig::default());
+        let config = Extension(Arc::new(config));
+        let student = ObjectId::new();
+        let mut practice = helpers::member(student, AttendanceStatus::Draft);
+        practice.mode = AttendanceMode::SocialPractice;
+        practice.duration = 20.0;
+        let activity_id = helpers::create_activity(&db, helpers::now(), vec![practice]).await;
+        let shared = Extension(Arc::new(Mutex::new(db.clone())));
+        let path = (activity_id.to_hex(), student.to_hex());
+        let update = |override_rules| {
+            Json(UpdateActivityMemberMode {
+                mode: AttendanceMode::OnCampus,
+                override_rules,
+            })
+        };
+        let rejected = update_member_mode(
+            shared.clone(),
+            config.clone(),
+            helpers::token(student, vec![GroupPermission::Student]),
+            Path(path.clone()),
+            update(None),
+        )
+        .await
+        .into_response();
+        let overridden = update_member_mode(
+            shared.clone(),
+            config,
+            helpers::token(ObjectId::new(), vec![GroupPermission::Admin]),
+            Path(path.clone()),
+            update(Some(true)),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(overridden.status(), StatusCode::OK);
+        let member = &activity.members.unwrap()[0];
+        assert_eq!(member.mode, AttendanceMode::OnCampus);
+        let history = member.history.clone().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].overridden, Some(true));
+    }
+    #[tokio::test]
+    async fn drafts_without_an_impression_cannot_be_submitted() {
+        let db = connect().await;
+        let config = load_or_init_config().await.unwrap();
+        let student = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(student, AttendanceStatus::Draft)],
+        )
+        .await;
+        let result = update_member_status(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            Extension(Arc::new(config)),
+            helpers::token(student, vec![GroupPermission::Student]),
+            Path((activity_id.to_hex(), student.to_hex())),
+            Json(UpdateActivityMemberStatus {
+                status: AttendanceStatus::Pending,
+                duration: None,
+                reason: None,
+                override_rules: None,
+            }),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+    #[tokio::test]
+    async fn removed_members_are_kept_with_their_history() {
+        let db = connect().await;
+        let student = ObjectId::new();
+        let other = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![
+                helpers::member(student, AttendanceStatus::Draft),
+                helpers::member(other, AttendanceStatus::Draft),
+            ],
+        )
+        .await;
+        let admin = ObjectId::new();
+        let result = remove_member_from_activity(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            helpers::token(admin, vec![GroupPermission::Admin]),
+            Path((activity_id.to_hex(), student.to_hex())),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let members = activity.members.unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0]._id, other);
+        let removed = activity.removed_members.unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0]._id, student);
+        let history = removed[0].history.clone().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].action, Some(AttendanceAction::Remove));
+        assert_eq!(history[0].actor, admin);
+    }
+    #[tokio::test]
+    async fn students_cannot_remove_others() {
+        let db = connect().await;
+        let student = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(student, AttendanceStatus::Draft)],
+        )
+        .await;
+        let result = remove_member_from_activity(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            helpers::token(ObjectId::new(), vec![GroupPermission::Student]),
+            Path((activity_id.to_hex(), student.to_hex())),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::FORBIDDEN);
+        assert_eq!(activity.members.unwrap().len(), 1);
+        assert_eq!(activity.removed_members, None);
+    }
+    #[tokio::test]
+    async fn removing_a_stranger_is_not_found() {
+        let db = connect().await;
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(ObjectId::new(), AttendanceStatus::Draft)],
+        )
+        .await;
+        let result = remove_member_from_activity(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            helpers::token(ObjectId::new(), vec![GroupPermission::Admin]),
+            Path((activity_id.to_hex(), ObjectId::new().to_hex())),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+        assert_eq!(result.status(), StatusCode::NOT_FOUND);
+    }
+    #[tokio::test]
+    async fn short_drafts_are_saved_with_a_warning() {
+        let db = connect().await;
+        let mut config = load_or_init_config().await.unwrap();
+        config.impressions = Some(ImpressionConfig::default());
+        let student = ObjectId::new();
+        let activity_id = helpers::create_activity(
+            &db,
+            helpers::now(),
+            vec![helpers::member(student, AttendanceStatus::Draft)],
+        )
+        .await;
+        let result = update_member_impression(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            Extension(Arc::new(config)),
+            helpers::token(student, vec![GroupPermission::Student]),
+            Path((activity_id.to_hex(), student.to_hex())),
+            Json(UpdateActivityMemberImpression {
+                impression: "太短".to_string(),
+            }),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let issues: Vec<ImpressionIssue> = helpers::read_data(result).await;
+        assert_eq!(issues, vec![ImpressionIssue::TooShort]);
+        let member = &activity.members.unwrap()[0];
+        assert_eq!(member.impression, Some("太短".to_string()));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod activity;
 pub mod auth;
+pub mod certificates;
+pub mod members;
+pub mod reviews;
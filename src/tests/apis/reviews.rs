@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database,
+        models::{
+            activities::{Activity, ActivityType},
+            attendances::{ActivityMember, AttendanceStatus},
+            groups::GroupPermission,
+            response::{MetadataSize, SuccessResponse},
+        },
+        routers::reviews::{
+            read_review_queue, review_members, ReadReviewQuery, ReviewDecision, ReviewItem,
+            ReviewMembers, ReviewReport, ReviewResult,
+        },
+        tests::helpers,
+        utils::{config::load_or_init_config, jwt::UserData},
+    };
+    use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+    use bson::{doc, oid::ObjectId};
+    use mongodb::Collection;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    /// 2100-01-01, so no real activity falls in the same range.
+    const DATE: u64 = 4102444800;
+
+    fn auditor() -> UserData {
+        helpers::token(ObjectId::new(), vec![GroupPermission::Auditor])
+    }
+
+    fn member(status: AttendanceStatus) -> ActivityMember {
+        ActivityMember {
+            impression: Some("测试".to_string()),
+            images: Some(vec!["images/test.webp".to_string()]),
+            ..helpers::member(ObjectId::new(), status)
+        }
+    }
+
+    #[tokio::test]
+    async fn queue_is_filtered_and_paged() {
+        let db = database::create_client().await.unwrap();
+        let config = load_or_init_config().await.unwrap();
+        let inside = helpers::create_activity(
+            &db,
+            DATE,
+            vec![
+                member(AttendanceStatus::Pending),
+                member(AttendanceStatus::Pending),
+                member(AttendanceStatus::Pending),
+                member(AttendanceStatus::Draft),
+            ],
+        )
+        .await;
+        let outside = helpers::create_activity(
+            &db,
+            DATE + 10 * 86400,
+            vec![member(AttendanceStatus::Pending)],
+        )
+        .await;
+        let result = read_review_queue(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            Extension(Arc::new(config)),
+            auditor(),
+            Query(ReadReviewQuery {
+                page: Some(2),
+                perpage: Some(2),
+                class: None,
+                activity_type: Some(ActivityType::Specified),
+                from: Some(DATE - 86400),
+                to: Some(DATE + 86400),
+            }),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        activities
+            .delete_many(doc! {"_id": {"$in": [inside, outside]}}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let response: SuccessResponse<Vec<ReviewItem>, MetadataSize> =
+            helpers::read_response(result).await;
+        assert_eq!(response.metadata.unwrap().size, 3);
+        assert_eq!(response.data.len(), 1);
+        let item = &response.data[0];
+        assert_eq!(item.activity, inside);
+        assert_eq!(item.member.images, None);
+        assert_eq!(item.images.len(), 1);
+        assert!(item.images[0].url.starts_with("/storage/images/test.webp?"));
+    }
+    #[tokio::test]
+    async fn reviews_report_every_member() {
+        let db = database::create_client().await.unwrap();
+        let pending = member(AttendanceStatus::Pending);
+        let draft = member(AttendanceStatus::Draft);
+        let activity_id =
+            helpers::create_activity(&db, DATE, vec![pending.clone(), draft.clone()]).await;
+        let decision = |member: String, reason: Option<&str>| ReviewDecision {
+            activity: activity_id.to_hex(),
+            member,
+            reason: reason.map(|reason| reason.to_string()),
+        };
+        let missing = ObjectId::new().to_hex();
+        let result = review_members(
+            Extension(Arc::new(Mutex::new(db.clone()))),
+            auditor(),
+            Json(ReviewMembers {
+                status: AttendanceStatus::Effective,
+                reason: Some("Shared".to_string()),
+                members: vec![
+                    decision(pending._id.to_hex(), Some("Well done")),
+                    decision(draft._id.to_hex(), None),
+                    decision(missing.clone(), None),
+                    decision("not-an-id".to_string(), None),
+                ],
+            }),
+        )
+        .await
+        .into_response();
+        let activities: Collection<Activity> = db.collection("activities");
+        let activity = activities
+            .find_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap()
+            .unwrap();
+        activities
+            .delete_one(doc! {"_id": activity_id}, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.status(), StatusCode::OK);
+        let response: SuccessResponse<Vec<ReviewReport>, ()> = helpers::read_response(result).await;
+        let results: Vec<(String, ReviewResult)> = response
+            .data
+            .into_iter()
+            .map(|report| (report.member, report.result))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                (pending._id.to_hex(), ReviewResult::Updated),
+                (draft._id.to_hex(), ReviewResult::NotPending),
+                (missing, ReviewResult::NotFound),
+                ("not-an-id".to_string(), ReviewResult::Invalid),
+            ]
+        );
+        let members = activity.members.unwrap();
+        assert_eq!(members[0].status, AttendanceStatus::Effective);
+        let history = members[0].history.clone().unwrap();
+        assert_eq!(
+            history.last().unwrap().reason,
+            Some("Well done".to_string())
+        );
+        assert_eq!(members[1].status, AttendanceStatus::Draft);
+    }
+    #[tokio::test]
+    async fn only_approval_and_refusal_are_accepted() {
+        let db = database::create_client().await.unwrap();
+        let result = review_members(
+            Extension(Arc::new(Mutex::new(db))),
+            auditor(),
+            Json(ReviewMembers {
+                status: AttendanceStatus::Draft,
+                reason: None,
+                members: vec![],
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(result.status(), StatusCode::BAD_REQUEST);
+    }
+}
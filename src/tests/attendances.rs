@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{
+            activities::ActivityType,
+            attendances::{AttendanceMode, AttendanceStatus},
+            groups::GroupPermission,
+        },
+        utils::{
+            attendances::{
+                duration::{check_duration, find_duration_rule, validate_duration, DurationError},
+                transition::{
+                    transition_role, transition_roles, validate_transition, TransitionError,
+                    TransitionRole,
+                },
+            },
+            config::{DurationConfig, DurationRule},
+        },
+    };
+    use axum::http::StatusCode;
+    use AttendanceStatus::*;
+
+    const STATUSES: [AttendanceStatus; 5] = [Effective, Pending, Refused, Rejected, Draft];
+
+    fn expected_role(from: &AttendanceStatus, to: &AttendanceStatus) -> Option<TransitionRole> {
+        match (from, to) {
+            (Draft, Draft) => Some(TransitionRole::Member),
+            (Draft, Pending) => Some(TransitionRole::Member),
+            (Rejected, Rejected) => Some(TransitionRole::Member),
+            (Rejected, Pending) => Some(TransitionRole::Member),
+            (Pending, Pending) => Some(TransitionRole::Auditor),
+            (Pending, Effective) => Some(TransitionRole::Auditor),
+            (Pending, Refused) => Some(TransitionRole::Auditor),
+            (Pending, Rejected) => Some(TransitionRole::Auditor),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn every_transition_has_expected_role() {
+        for from in STATUSES.iter() {
+            for to in STATUSES.iter() {
+                assert_eq!(
+                    transition_role(from, to),
+                    expected_role(from, to),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_transition_is_role_gated() {
+        let member = [TransitionRole::Member];
+        let auditor = [TransitionRole::Auditor];
+        for from in STATUSES.iter() {
+            for to in STATUSES.iter() {
+                let (as_member, as_auditor) = match expected_role(from, to) {
+                    None => (Err(TransitionError::Invalid), Err(TransitionError::Invalid)),
+                    Some(TransitionRole::Member) => (Ok(()), Err(TransitionError::Forbidden)),
+                    Some(TransitionRole::Auditor) => (Err(TransitionError::Forbidden), Ok(())),
+                };
+                assert_eq!(validate_transition(from, to, &member), as_member);
+                assert_eq!(validate_transition(from, to, &auditor), as_auditor);
+                assert_eq!(
+                    validate_transition(from, to, &[]),
+                    as_member.clone().and(as_auditor.clone())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn final_states_are_final() {
+        let roles = [TransitionRole::Member, TransitionRole::Auditor];
+        for from in [Effective, Refused].iter() {
+            for to in STATUSES.iter() {
+                assert_eq!(
+                    validate_transition(from, to, &roles),
+                    Err(TransitionError::Invalid)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn roles_from_permissions() {
+        assert_eq!(
+            transition_roles(true, &[GroupPermission::Student]),
+            vec![TransitionRole::Member]
+        );
+        assert_eq!(
+            transition_roles(false, &[GroupPermission::Auditor]),
+            vec![TransitionRole::Auditor]
+        );
+        assert_eq!(
+            transition_roles(true, &[GroupPermission::Admin]),
+            vec![TransitionRole::Member, TransitionRole::Auditor]
+        );
+        assert!(transition_roles(false, &[GroupPermission::Secretary]).is_empty());
+    }
+
+    #[test]
+    fn status_is_stored_as_plain_string() {
+        assert_eq!(
+            bson::to_bson(&Pending).unwrap(),
+            bson::Bson::String("pending".to_string())
+        );
+    }
+    fn rule(
+        activity_type: Option<ActivityType>,
+        mode: Option<AttendanceMode>,
+        max: f64,
+    ) -> DurationRule {
+        DurationRule {
+            activity_type,
+            mode,
+            max,
+            step: 0.5,
+        }
+    }
+
+    #[test]
+    fn most_specific_duration_rule_wins() {
+        let config = DurationConfig {
+            rules: vec![
+                rule(
+                    Some(ActivityType::Social),
+                    Some(AttendanceMode::OffCampus),
+                    4.0,
+                ),
+                rule(None, None, 10.0),
+                rule(None, Some(AttendanceMode::OffCampus), 6.0),
+                rule(Some(ActivityType::Social), None, 8.0),
+            ],
+        };
+        let max = |activity_type, mode| {
+            find_duration_rule(&config, &activity_type, &mode).map(|rule| rule.max)
+        };
+        assert_eq!(
+            max(ActivityType::Social, AttendanceMode::OffCampus),
+            Some(4.0)
+        );
+        assert_eq!(
+            max(ActivityType::Social, AttendanceMode::OnCampus),
+            Some(8.0)
+        );
+        assert_eq!(
+            max(ActivityType::Scale, AttendanceMode::OffCampus),
+            Some(6.0)
+        );
+        assert_eq!(
+            max(ActivityType::Scale, AttendanceMode::OnCampus),
+            Some(10.0)
+        );
+        let empty = DurationConfig { rules: vec![] };
+        assert!(
+            find_duration_rule(&empty, &ActivityType::Scale, &AttendanceMode::OnCampus).is_none()
+        );
+    }
+    #[test]
+    fn special_activities_are_only_limited_by_their_own_rules() {
+        let config = DurationConfig::default();
+        let special = |mode| find_duration_rule(&config, &ActivityType::Special, &mode);
+        assert!(special(AttendanceMode::OnCampus).is_none());
+        assert!(special(AttendanceMode::SocialPractice).is_none());
+        let config = DurationConfig {
+            rules: vec![
+                rule(None, None, 10.0),
+                rule(Some(ActivityType::Special), None, 100.0),
+            ],
+        };
+        assert_eq!(
+            find_duration_rule(&config, &ActivityType::Special, &AttendanceMode::OnCampus)
+                .map(|rule| rule.max),
+            Some(100.0)
+        );
+    }
+    #[test]
+    fn durations_follow_cap_and_step() {
+        let rule = rule(None, None, 4.0);
+        assert_eq!(validate_duration(Some(&rule), 0.0), Ok(()));
+        assert_eq!(validate_duration(Some(&rule), 3.5), Ok(()));
+        assert_eq!(validate_duration(Some(&rule), 4.0), Ok(()));
+        assert_eq!(
+            validate_duration(Some(&rule), 4.5),
+            Err(DurationError::TooLong(4.0))
+        );
+        assert_eq!(
+            validate_duration(Some(&rule), 1.2),
+            Err(DurationError::Granularity(0.5))
+        );
+        assert_eq!(
+            validate_duration(Some(&rule), -1.0),
+            Err(DurationError::Negative)
+        );
+        assert_eq!(validate_duration(None, 100.3), Ok(()));
+    }
+    #[test]
+    fn only_admins_override_duration_rules() {
+        let config = DurationConfig::default();
+        let check = |duration, override_rules, perms: &[GroupPermission]| {
+            check_duration(
+                &config,
+                &ActivityType::Specified,
+                &AttendanceMode::OnCampus,
+                duration,
+                override_rules,
+                perms,
+            )
+            .map_err(|(code, _)| code)
+        };
+        let admin = [GroupPermission::Admin];
+        let auditor = [GroupPermission::Auditor];
+        assert_eq!(check(2.0, false, &auditor), Ok(false));
+        assert_eq!(check(50.0, false, &auditor), Err(StatusCode::BAD_REQUEST));
+        assert_eq!(check(50.0, true, &auditor), Err(StatusCode::FORBIDDEN));
+        assert_eq!(check(50.0, true, &admin), Ok(true));
+        assert_eq!(check(2.0, true, &admin), Ok(false));
+        assert_eq!(check(-1.0, true, &admin), Err(StatusCode::BAD_REQUEST));
+    }
+}
@@ -23,17 +23,13 @@ mod tests {
             timestamp: timestamp as u64,
         };
         let credential = serde_json::to_string(&payload);
-        if let Err(_) = credential {
-            assert!(false);
-        }
+        assert!(credential.is_ok());
         let credential = credential.unwrap();
         let (private_key, public_key) = generate_keypair().await;
         let encrypted = encrypt(&public_key, credential.as_str());
         let decrypted = decrypt(&private_key, &encrypted).await;
-        let decrypted = serde_json::from_str(&decrypted.as_str());
-        if let Err(_) = decrypted {
-            assert!(false);
-        }
+        let decrypted = serde_json::from_str(decrypted.as_str());
+        assert!(decrypted.is_ok());
         let decrypted = decrypted.unwrap();
         println!("Decrypted: {:#?}", &decrypted);
         assert_eq!(payload, decrypted);
@@ -46,9 +42,7 @@ mod tests {
         let token = token.as_str();
         println!("Token: {:?}", token);
         let result = verify_token(token.to_string());
-        if let Err(_) = result {
-            assert!(false);
-        }
+        assert!(result.is_ok());
         let result = result.unwrap();
         assert_eq!(result.sub, sub);
         assert_eq!(result.perms, perms);
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        routers::users::time::UserActivityTimes,
+        utils::certificates::{
+            generate_code, hash_document, normalize_code,
+            pdf::{render_certificate, CertificateActivity, CertificateContent},
+        },
+    };
+
+    /// Any TrueType font works for layout; CJK glyphs are only needed to see them.
+    const FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+    fn content(activities: usize) -> CertificateContent {
+        let mut times = UserActivityTimes::default();
+        times.add("effective", "on-campus", "", 12.0);
+        times.add("effective", "off-campus", "prize", 3.0);
+        CertificateContent {
+            issuer: "ZVMS".to_string(),
+            name: "Zhang San".to_string(),
+            id: "20240101".to_string(),
+            class: "Class 1".to_string(),
+            period: "All time".to_string(),
+            issued: "2024-06-01".to_string(),
+            code: generate_code(),
+            time: times.effective,
+            activities: (0..activities)
+                .map(|i| CertificateActivity {
+                    date: "2024-03-01".to_string(),
+                    name: format!("Tree planting {}", i),
+                    mode: "on-campus".to_string(),
+                    duration: 1.5,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn codes_are_grouped() {
+        let code = generate_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(&code[5..6], "-");
+        assert!(code
+            .chars()
+            .all(|c| c == '-' || c.is_ascii_digit() || c.is_ascii_uppercase()));
+        assert!(!code.contains(['I', 'L', 'O', 'U']));
+        assert_ne!(generate_code(), code);
+    }
+    #[test]
+    fn invalid_fonts_are_rejected() {
+        assert!(render_certificate(b"not a font", &content(1)).is_err());
+    }
+    #[test]
+    fn certificates_are_rendered() {
+        let font = std::fs::read(FONT);
+        if font.is_err() {
+            // The font is not installed everywhere the tests run
+            return;
+        }
+        let font = font.unwrap();
+        let short = render_certificate(&font, &content(1)).unwrap();
+        assert!(short.starts_with(b"%PDF"));
+        // Long histories continue on further pages
+        let long = render_certificate(&font, &content(80)).unwrap();
+        assert!(long.len() > short.len());
+    }
+    #[test]
+    fn typed_codes_are_normalized() {
+        assert_eq!(normalize_code("7k2qx m9c4o"), "7K2QX-M9C40");
+        assert_eq!(normalize_code("7K2QX-M9C4L"), "7K2QX-M9C41");
+        assert_eq!(normalize_code("abc"), "ABC");
+        let code = generate_code();
+        assert_eq!(normalize_code(&code.to_lowercase()), code);
+    }
+    #[test]
+    fn documents_are_hashed() {
+        assert_eq!(
+            hash_document(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
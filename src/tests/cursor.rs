@@ -0,0 +1,17 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::cursor::{decode_cursor, encode_cursor};
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn cursor_round_trip() {
+        let id = ObjectId::new();
+        let cursor = encode_cursor(&id);
+        assert_eq!(decode_cursor(&cursor), Ok(id));
+    }
+    #[test]
+    fn invalid_cursor() {
+        assert!(decode_cursor("not a cursor").is_err());
+        assert!(decode_cursor("AAAA").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        config::{Config, Term},
+        dates::{config_timezone, DateRange, DateRangeQuery},
+    };
+    use chrono::FixedOffset;
+
+    fn config() -> Config {
+        Config {
+            timezone: "8".to_string(),
+            terms: Some(vec![Term {
+                name: "2024-fall".to_string(),
+                start: "2024-09-01".to_string(),
+                end: "2025-01-31".to_string(),
+            }]),
+            ..Default::default()
+        }
+    }
+
+    // 2024-09-01 00:00:00 and 2025-01-31 23:59:59 at UTC+8
+    const TERM_START: u64 = 1725120000;
+    const TERM_END: u64 = 1738339199;
+
+    #[test]
+    fn term_resolves_in_configured_timezone() {
+        let query = DateRangeQuery {
+            term: Some("2024-fall".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            query.resolve(&config()),
+            Ok(DateRange {
+                from: Some(TERM_START),
+                to: Some(TERM_END),
+            })
+        );
+    }
+    #[test]
+    fn term_is_narrowed_by_explicit_bounds() {
+        let query = DateRangeQuery {
+            from: Some(TERM_START - 100),
+            to: Some(TERM_START + 100),
+            term: Some("2024-fall".to_string()),
+        };
+        assert_eq!(
+            query.resolve(&config()),
+            Ok(DateRange {
+                from: Some(TERM_START),
+                to: Some(TERM_START + 100),
+            })
+        );
+    }
+    #[test]
+    fn unknown_term_is_rejected() {
+        let query = DateRangeQuery {
+            term: Some("1999-spring".to_string()),
+            ..Default::default()
+        };
+        assert!(query.resolve(&config()).is_err());
+    }
+    #[test]
+    fn unbounded_range_adds_no_stages() {
+        let timezone = config_timezone(&config());
+        assert_eq!(timezone, FixedOffset::east_opt(8 * 3600).unwrap());
+        assert!(DateRange::default().stages(timezone).is_empty());
+        let range = DateRange {
+            from: Some(1),
+            to: None,
+        };
+        let stages = range.stages(timezone);
+        assert_eq!(stages.len(), 2);
+        assert!(stages[0].to_string().contains("+08:00"));
+    }
+}
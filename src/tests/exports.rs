@@ -0,0 +1,351 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::exports::{ExportActivityTimesOptions, ExportFormat},
+        routers::exports::{export_range, socket_user},
+        utils::exports::{
+            export_csv::{export_pipeline, save_to_csv, ExportColumns, ExportRow},
+            export_excel::{save_to_excel, sheet_name, split_by_column},
+            export_json::dataframe_to_json,
+            export_roster::{format_date, roster_pipeline, RosterColumns, RosterRow},
+            schedules::{next_run, parse_cron, scheduled_task_notification},
+            tasks::{download_url, export_file},
+        },
+    };
+    use crate::{
+        models::{
+            exports::{ExportKind, Task, TaskStatus},
+            groups::GroupPermission,
+            schedules::ExportSchedule,
+        },
+        utils::{
+            dates::DateRange,
+            jwt::{generate_token, TokenType},
+        },
+    };
+    use bson::{doc, oid::ObjectId, Bson};
+    use chrono::FixedOffset;
+    use polars::df;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn sheet_names_are_valid() {
+        assert_eq!(sheet_name("高一 (1) 班"), "高一 (1) 班");
+        assert_eq!(sheet_name("2023/1"), "2023_1");
+        assert_eq!(sheet_name("  "), "Unassigned");
+        assert_eq!(sheet_name(&"a".repeat(40)).len(), 31);
+    }
+    #[test]
+    fn rows_are_split_by_column() {
+        let df = df!(
+            "name" => &["A", "B", "C"],
+            "class" => &["2", "1", "2"],
+            "total" => &[1.0, 2.0, 3.0]
+        )
+        .unwrap();
+        let sheets = split_by_column(&df, "class").unwrap();
+        assert_eq!(sheets.keys().collect::<Vec<_>>(), vec!["1", "2"]);
+        assert_eq!(sheets["2"], vec![0, 2]);
+    }
+    #[tokio::test]
+    async fn workbook_is_written() {
+        let df = df!(
+            "name" => &["A", "B"],
+            "class" => &["1", ""],
+            "total" => &[1.5, 2.0]
+        )
+        .unwrap();
+        let file = NamedTempFile::new().unwrap();
+        save_to_excel(df, "class", file.as_file()).await.unwrap();
+        let data = std::fs::read(file.path()).unwrap();
+        assert!(data.starts_with(b"PK"));
+    }
+    #[test]
+    fn open_bounds_are_ignored() {
+        let options = ExportActivityTimesOptions {
+            start: 0,
+            end: 1700000000,
+            format: ExportFormat::Csv,
+        };
+        let range = export_range(&options);
+        assert_eq!(range.from, None);
+        assert_eq!(range.to, Some(1700000000));
+        assert_eq!(options.format.extension(), "csv");
+    }
+    #[test]
+    fn old_format_names_are_accepted() {
+        let format: ExportFormat = serde_json::from_str("\"c-s-v\"").unwrap();
+        assert_eq!(format, ExportFormat::Csv);
+        let format: ExportFormat = serde_json::from_str("\"j-s-o-n\"").unwrap();
+        assert_eq!(format, ExportFormat::Json);
+        assert_eq!(
+            serde_json::to_string(&ExportFormat::Csv).unwrap(),
+            "\"csv\""
+        );
+    }
+    #[test]
+    fn rows_are_converted_to_json() {
+        let df = df!(
+            "name" => &["A"],
+            "total" => &[1.5]
+        )
+        .unwrap();
+        let json = dataframe_to_json(&df).unwrap();
+        assert_eq!(json, serde_json::json!([{"name": "A", "total": 1.5}]));
+    }
+    #[tokio::test]
+    async fn csv_starts_with_bom() {
+        let df = df!(
+            "name" => &["张三"],
+            "total" => &[1.5]
+        )
+        .unwrap();
+        let file = NamedTempFile::new().unwrap();
+        save_to_csv(df, file.as_file()).await.unwrap();
+        let data = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(data, "\u{feff}name,total\n张三,1.5\n");
+    }
+    #[test]
+    fn rows_from_the_pipeline_are_summed() {
+        let row: ExportRow = bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "id": "20240101",
+            "name": "张三",
+            "classes": ["高一 (1) 班"],
+            "times": [
+                {"mode": "on-campus", "category": "", "duration": 2.5},
+                {"mode": "off-campus", "category": "import", "duration": 3},
+                {"mode": "on-campus", "category": "deduction", "duration": 1.0},
+                {"mode": "on-campus", "category": Bson::Null, "duration": 2},
+            ],
+        })
+        .unwrap();
+        let time = row.time();
+        assert_eq!(row.class(), "高一 (1) 班");
+        assert_eq!(time.on_campus, 3.5);
+        assert_eq!(time.special.historical, 3.0);
+        assert_eq!(time.total, 6.5);
+    }
+    #[test]
+    fn users_without_activity_are_exported_with_zeros() {
+        let row: ExportRow = bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "id": "20240102",
+            "name": "李四",
+            "classes": [],
+            "times": [{}],
+        })
+        .unwrap();
+        let mut columns = ExportColumns::default();
+        columns.push_row(&row);
+        let df = columns.into_dataframe().unwrap();
+        assert_eq!(df.height(), 1);
+        assert_eq!(df.column("total").unwrap().f64().unwrap().get(0), Some(0.0));
+        assert_eq!(df.column("class").unwrap().str().unwrap().get(0), Some(""));
+    }
+    #[test]
+    fn export_files_are_named_by_task() {
+        let task_id = "8d3c5a2e-6a0b-4f55-9d38-0f3c1f5e4b21";
+        let file = export_file(task_id, &ExportFormat::Excel);
+        assert_eq!(
+            file.to_str().unwrap(),
+            "public/exports/8d3c5a2e-6a0b-4f55-9d38-0f3c1f5e4b21.xlsx"
+        );
+        assert_eq!(
+            download_url(task_id),
+            "/export/8d3c5a2e-6a0b-4f55-9d38-0f3c1f5e4b21/download"
+        );
+    }
+    #[test]
+    fn roster_dates_are_formatted() {
+        let timezone = FixedOffset::east_opt(8 * 3600).unwrap();
+        assert_eq!(
+            format_date(&Bson::Int64(1700000000), timezone),
+            "2023-11-15"
+        );
+        assert_eq!(
+            format_date(&Bson::String("2024-03-01 08:00:00".to_string()), timezone),
+            "2024-03-01"
+        );
+        assert_eq!(format_date(&Bson::Null, timezone), "");
+    }
+    #[test]
+    fn roster_pipeline_filters_activities() {
+        let timezone = FixedOffset::east_opt(0).unwrap();
+        let activity = ObjectId::new();
+        let pipeline = roster_pipeline(&[activity], &DateRange::default(), timezone);
+        assert_eq!(pipeline[0], doc! {"$match": {"_id": {"$in": [activity]}}});
+        let pipeline = roster_pipeline(&[], &DateRange::default(), timezone);
+        assert_eq!(pipeline[0], doc! {"$sort": {"date": 1, "_id": 1}});
+    }
+    #[test]
+    fn roster_rows_become_columns() {
+        let row: RosterRow = bson::from_document(doc! {
+            "activity": "植树",
+            "date": 1700000000_i64,
+            "id": "20240101",
+            "name": "张三",
+            "classes": ["高一 (1) 班"],
+            "mode": "off-campus",
+            "duration": 2,
+            "status": "effective",
+        })
+        .unwrap();
+        let mut columns = RosterColumns::default();
+        columns.push(row, FixedOffset::east_opt(8 * 3600).unwrap());
+        let df = columns.into_dataframe().unwrap();
+        assert_eq!(
+            df.column("duration").unwrap().f64().unwrap().get(0),
+            Some(2.0)
+        );
+        assert_eq!(
+            df.column("impression").unwrap().str().unwrap().get(0),
+            Some("")
+        );
+        assert_eq!(
+            ExportKind::Roster { activities: vec![] }.sheet_column(),
+            "activity"
+        );
+    }
+    #[test]
+    fn class_exports_only_keep_class_members() {
+        let timezone = FixedOffset::east_opt(0).unwrap();
+        let class = ObjectId::new();
+        let all = export_pipeline(&[], &DateRange::default(), timezone);
+        let scoped = export_pipeline(&[class], &DateRange::default(), timezone);
+        assert_eq!(scoped.len(), all.len() + 1);
+        assert!(scoped.contains(&doc! {"$match": {"user.group": {"$in": [class]}}}));
+        let union = scoped
+            .iter()
+            .find_map(|stage| stage.get_document("$unionWith").ok())
+            .unwrap();
+        assert_eq!(
+            union.get_array("pipeline").unwrap()[0],
+            Bson::Document(doc! {"$match": {"group": {"$in": [class]}}})
+        );
+    }
+    #[test]
+    fn cron_expressions_are_validated() {
+        assert!(parse_cron("0 8 * * MON").is_ok());
+        assert!(parse_cron("@weekly").is_ok());
+        assert!(parse_cron("0 8 * *").is_err());
+        assert!(parse_cron("0 0 8 * * MON").is_err());
+        assert!(parse_cron("61 8 * * *").is_err());
+    }
+
+    fn schedule() -> ExportSchedule {
+        ExportSchedule {
+            _id: ObjectId::new(),
+            owner: ObjectId::new(),
+            cron: "0 8 * * MON".to_string(),
+            kind: ExportKind::ActivityTimes,
+            format: ExportFormat::Excel,
+            days: Some(7),
+            keep: 4,
+            next_run: 1717372800,
+            last_run: None,
+        }
+    }
+    #[test]
+    fn schedules_run_in_the_configured_timezone() {
+        let timezone = FixedOffset::east_opt(8 * 3600).unwrap();
+        // Saturday 2024-06-01 00:00 UTC to Monday 08:00 in UTC+8
+        assert_eq!(
+            next_run("0 8 * * MON", 1717200000, timezone).unwrap(),
+            1717372800
+        );
+        // A run never repeats at the time it was started
+        assert_eq!(
+            next_run("0 8 * * MON", 1717372800, timezone).unwrap(),
+            1717372800 + 7 * 86400
+        );
+    }
+    #[test]
+    fn numeric_weekdays_count_from_sunday() {
+        let timezone = FixedOffset::east_opt(8 * 3600).unwrap();
+        // Saturday 2024-06-01 00:00 UTC to Monday 08:00 in UTC+8
+        assert_eq!(
+            next_run("0 8 * * 1", 1717200000, timezone).unwrap(),
+            1717372800
+        );
+        // Sunday is both 0 and 7
+        assert_eq!(
+            next_run("0 8 * * 0", 1717200000, timezone).unwrap(),
+            1717372800 - 86400
+        );
+        assert_eq!(
+            next_run("0 8 * * 7", 1717200000, timezone).unwrap(),
+            1717372800 - 86400
+        );
+        // Weekdays only, so Saturday is skipped
+        assert_eq!(
+            next_run("0 8 * * 1-5", 1717200000, timezone).unwrap(),
+            1717372800
+        );
+        // Friday through Sunday, after the Saturday run at the start
+        assert_eq!(
+            next_run("0 8 * * 5-7", 1717200000, timezone).unwrap(),
+            1717372800 - 86400
+        );
+        assert!(parse_cron("0 8 * * 8").is_err());
+    }
+    #[test]
+    fn day_of_month_and_weekday_must_both_match() {
+        let timezone = FixedOffset::east_opt(8 * 3600).unwrap();
+        // The first Friday the 13th after 2024-06-01 is in September
+        assert_eq!(
+            next_run("0 0 13 * FRI", 1717200000, timezone).unwrap(),
+            1726156800
+        );
+    }
+    #[test]
+    fn scheduled_runs_cover_the_last_days() {
+        let mut schedule = schedule();
+        let options = schedule.options(1717372800);
+        assert_eq!(options.start, 1717372800 - 7 * 86400);
+        assert_eq!(options.end, 1717372800);
+        assert_eq!(options.format, ExportFormat::Excel);
+        schedule.days = None;
+        assert_eq!(schedule.options(1717372800).start, 0);
+    }
+    #[test]
+    fn owners_are_notified_of_scheduled_runs() {
+        let mut schedule = schedule();
+        // Already moved on to the following Monday
+        schedule.next_run += 7 * 86400;
+        let mut task = Task {
+            _id: "task".to_string(),
+            time: 1717372800000,
+            actioner: schedule.owner,
+            kind: ExportKind::ActivityTimes,
+            options: schedule.options(1717372800),
+            status: TaskStatus::Done,
+            result: Some(download_url("task")),
+            content_type: None,
+            percent: Some(100.0),
+            schedule: Some(schedule._id),
+        };
+        let notification = scheduled_task_notification(&schedule, &task, 1717372860);
+        assert_eq!(notification.receivers, Some(vec![schedule.owner]));
+        assert!(notification
+            .content
+            .unwrap()
+            .contains("/export/task/download"));
+        assert_eq!(notification.expire, schedule.next_run);
+        task.status = TaskStatus::Error;
+        task.result = None;
+        let notification = scheduled_task_notification(&schedule, &task, 1717372860);
+        assert_eq!(notification.title, "Scheduled export failed");
+        assert_eq!(notification.expire, schedule.next_run);
+    }
+    #[test]
+    fn sockets_subscribe_with_a_valid_token() {
+        let id = ObjectId::new().to_hex();
+        let token = generate_token(&id, TokenType::ShortTerm, vec![GroupPermission::Admin]);
+        let user = socket_user(&serde_json::json!({"token": token})).unwrap();
+        assert_eq!(user.id, id);
+        assert_eq!(user.perms, vec![GroupPermission::Admin]);
+        assert!(socket_user(&serde_json::json!({"token": "not-a-token"})).is_none());
+        assert!(socket_user(&serde_json::json!({})).is_none());
+    }
+}
@@ -0,0 +1,85 @@
+//! Fixtures shared by the tests.
+
+use crate::{
+    models::{
+        activities::{Activity, ActivityStatus, ActivityType},
+        attendances::{ActivityMember, AttendanceMode, AttendanceStatus},
+        groups::GroupPermission,
+        response::SuccessResponse,
+    },
+    utils::jwt::{TokenType, UserData},
+};
+use axum::response::Response;
+use bson::oid::ObjectId;
+use mongodb::{Collection, Database};
+use serde::de::DeserializeOwned;
+use std::time::SystemTime;
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+pub fn token(id: ObjectId, perms: Vec<GroupPermission>) -> UserData {
+    UserData {
+        id: id.to_hex(),
+        perms,
+        term: TokenType::LongTerm,
+    }
+}
+
+pub fn member(id: ObjectId, status: AttendanceStatus) -> ActivityMember {
+    ActivityMember {
+        _id: id,
+        status,
+        impression: None,
+        duration: 1.0,
+        mode: AttendanceMode::OnCampus,
+        history: Some(vec![]),
+        images: Some(vec![]),
+        image_size: Some(0),
+    }
+}
+
+/// An activity on `date` that tests insert and delete again.
+pub fn activity(date: u64, members: Vec<ActivityMember>) -> Activity {
+    Activity {
+        _id: ObjectId::new(),
+        activity_type: ActivityType::Specified,
+        name: "测试".to_string(),
+        description: Some("该义工为单元测试时自动创建，若出现长久放置请联系开发者。".to_string()),
+        date,
+        created_at: date,
+        updated_at: date,
+        creator: ObjectId::new(),
+        status: ActivityStatus::Effective,
+        members: Some(members),
+        removed_members: None,
+        location: None,
+        category: None,
+    }
+}
+
+pub async fn create_activity(db: &Database, date: u64, members: Vec<ActivityMember>) -> ObjectId {
+    let collection: Collection<Activity> = db.collection("activities");
+    let activity = activity(date, members);
+    collection.insert_one(&activity, None).await.unwrap();
+    activity._id
+}
+
+pub async fn read_response<T: DeserializeOwned, M: DeserializeOwned>(
+    response: Response,
+) -> SuccessResponse<T, M> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    // Handlers wrap the serialized response in a JSON string
+    let body: String = serde_json::from_slice(&body).unwrap();
+    serde_json::from_str(&body).unwrap()
+}
+
+pub async fn read_data<T: DeserializeOwned>(response: Response) -> T {
+    read_response::<T, ()>(response).await.data
+}
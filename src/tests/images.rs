@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::images::{
+        detect_mime, rendition_keys, strip_metadata, thumbnail::create_renditions,
+    };
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend(((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend(payload);
+        segment
+    }
+
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        // Little endian TIFF with orientation and a camera make entry
+        let mut payload = b"Exif\0\0II\x2a\0\x08\0\0\0".to_vec();
+        payload.extend(2u16.to_le_bytes());
+        payload.extend([0x0F, 0x01, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00]);
+        payload.extend(b"Cam\0");
+        payload.extend([0x12, 0x01, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        payload.extend(orientation.to_le_bytes());
+        payload.extend([0, 0, 0, 0, 0, 0]);
+        payload
+    }
+
+    fn jpeg(exif: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        data.extend(segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+        data.extend(segment(0xE1, exif));
+        data.extend(segment(0xE1, b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>"));
+        data.extend(segment(0xFE, b"secret comment"));
+        data.extend(segment(0xDB, &[0; 65]));
+        data.extend(segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+        data.extend([0x12, 0x34, 0xFF, 0x00, 0x56, 0xFF, 0xD9]);
+        data
+    }
+
+    fn png() -> Vec<u8> {
+        let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        for (chunk_type, payload) in [
+            (b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0][..]),
+            (b"tEXt", &b"Author\0someone"[..]),
+            (b"eXIf", &b"MM\0\x2a"[..]),
+            (
+                b"IDAT",
+                &[0x78, 0x9C, 0x63, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01][..],
+            ),
+            (b"IEND", &[][..]),
+        ] {
+            data.extend((payload.len() as u32).to_be_bytes());
+            data.extend(chunk_type);
+            data.extend(payload);
+            data.extend([0; 4]);
+        }
+        data
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn strip_jpeg_keeps_orientation() {
+        let data = jpeg(&exif_with_orientation(6));
+        let stripped = strip_metadata("image/jpeg", &data).unwrap();
+        assert!(!contains(&stripped, b"Cam"));
+        assert!(!contains(&stripped, b"xmpmeta"));
+        assert!(!contains(&stripped, b"secret comment"));
+        assert!(contains(&stripped, b"JFIF"));
+        assert!(contains(
+            &stripped,
+            &[0x01, 0x12, 0x00, 0x03, 0, 0, 0, 1, 0x00, 0x06]
+        ));
+        assert!(stripped.ends_with(&[0x12, 0x34, 0xFF, 0x00, 0x56, 0xFF, 0xD9]));
+    }
+    #[test]
+    fn strip_jpeg_drops_default_orientation() {
+        let data = jpeg(&exif_with_orientation(1));
+        let stripped = strip_metadata("image/jpeg", &data).unwrap();
+        assert!(!contains(&stripped, b"Exif"));
+    }
+    #[test]
+    fn strip_png_drops_text_chunks() {
+        let stripped = strip_metadata("image/png", &png()).unwrap();
+        assert!(!contains(&stripped, b"tEXt"));
+        assert!(!contains(&stripped, b"eXIf"));
+        assert!(contains(&stripped, b"IDAT"));
+        assert!(contains(&stripped, b"IEND"));
+    }
+    #[test]
+    fn strip_rejects_invalid_images() {
+        assert!(strip_metadata("image/jpeg", b"not a jpeg").is_err());
+        assert!(strip_metadata("image/png", b"not a png").is_err());
+        assert!(strip_metadata("image/gif", b"GIF89a").is_err());
+        let mut truncated = jpeg(&exif_with_orientation(6));
+        truncated.truncate(30);
+        assert!(strip_metadata("image/jpeg", &truncated).is_err());
+    }
+    #[test]
+    fn detect_image_mime() {
+        assert_eq!(detect_mime(&png()).unwrap(), "image/png");
+        assert_eq!(
+            detect_mime(&jpeg(&exif_with_orientation(6))).unwrap(),
+            "image/jpeg"
+        );
+        assert_eq!(detect_mime(b"plain text").unwrap(), "text/plain");
+    }
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut data = Cursor::new(vec![]);
+        image.write_to(&mut data, format).unwrap();
+        data.into_inner()
+    }
+
+    fn webp_dimensions(data: &[u8]) -> (u32, u32) {
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(&data[8..12], b"WEBP");
+        let image = webp::Decoder::new(data).decode().unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn renditions_are_downscaled_webp() {
+        let renditions = create_renditions(&encode(800, 400, ImageFormat::Png), 100, 400).unwrap();
+        assert_eq!(webp_dimensions(&renditions.thumbnail), (100, 50));
+        assert_eq!(webp_dimensions(&renditions.preview), (400, 200));
+    }
+    #[test]
+    fn renditions_are_not_upscaled() {
+        let renditions = create_renditions(&encode(60, 30, ImageFormat::Jpeg), 100, 400).unwrap();
+        assert_eq!(webp_dimensions(&renditions.thumbnail), (60, 30));
+        assert_eq!(webp_dimensions(&renditions.preview), (60, 30));
+    }
+    #[test]
+    fn renditions_follow_orientation() {
+        let data = encode(60, 30, ImageFormat::Jpeg);
+        let mut rotated = data[0..2].to_vec();
+        rotated.extend(segment(0xE1, &exif_with_orientation(6)));
+        rotated.extend(&data[2..]);
+        let rotated = strip_metadata("image/jpeg", &rotated).unwrap();
+        let renditions = create_renditions(&rotated, 100, 400).unwrap();
+        assert_eq!(webp_dimensions(&renditions.preview), (30, 60));
+    }
+    #[test]
+    fn renditions_reject_garbage() {
+        assert!(create_renditions(b"not an image", 100, 400).is_err());
+    }
+    #[test]
+    fn rendition_key_names() {
+        assert_eq!(
+            rendition_keys("members/a/b/c.jpg"),
+            (
+                "members/a/b/c.thumb.webp".to_string(),
+                "members/a/b/c.preview.webp".to_string()
+            )
+        );
+    }
+}
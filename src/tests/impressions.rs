@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        config::{CheckMode, ImpressionConfig},
+        impressions::{
+            check_impression, impression_length, rejection_message, similarity, ImpressionIssue,
+        },
+    };
+
+    const IMPRESSION: &str = "今天我们去社区参加了垃圾分类宣传活动，向居民讲解了可回收物、有害垃圾、厨余垃圾和其他垃圾的区别，\
+        很多老人一开始不太理解，但经过耐心的解释后都愿意尝试，我也体会到了志愿服务的意义。";
+    const EDITED: &str = "今天我们去社区参加了垃圾分类宣传活动，向居民讲解了可回收物、有害垃圾、厨余垃圾和其他垃圾的区别，\
+        不少老人一开始不太理解，但经过耐心的解释后都愿意尝试，我也体会到了志愿服务的意义！";
+    const OTHER: &str = "周末在图书馆整理书架，把归还的图书按照索书号放回原位，还帮助几位同学找到了需要的参考资料，\
+        虽然工作比较枯燥，但让我学会了细心和耐心，也了解了图书馆的分类方法。";
+
+    #[test]
+    fn length_is_cjk_aware() {
+        assert_eq!(impression_length("志愿服务"), 4);
+        assert_eq!(impression_length("I helped clean the park."), 5);
+        assert_eq!(impression_length("我 helped 了 3 位老人!"), 7);
+        assert_eq!(impression_length("  ，。!! "), 0);
+    }
+    #[test]
+    fn similarity_detects_near_duplicates() {
+        assert_eq!(similarity(IMPRESSION, IMPRESSION), 1.0);
+        assert!(similarity(IMPRESSION, EDITED) >= 0.9);
+        assert!(similarity(IMPRESSION, OTHER) < 0.8);
+        assert_eq!(similarity("Cleaned the park.", "cleaned  the PARK"), 1.0);
+    }
+    #[test]
+    fn check_reports_issues() {
+        let config = ImpressionConfig::default();
+        let others = vec![OTHER.to_string()];
+        assert!(check_impression(&config, IMPRESSION, &others, &others).is_empty());
+        assert_eq!(
+            check_impression(&config, "很好", &[], &[]),
+            vec![ImpressionIssue::TooShort]
+        );
+        assert_eq!(
+            check_impression(&config, IMPRESSION, &[EDITED.to_string()], &others),
+            vec![ImpressionIssue::DuplicateInActivity]
+        );
+        assert_eq!(
+            check_impression(&config, IMPRESSION, &others, &[IMPRESSION.to_string()]),
+            vec![ImpressionIssue::DuplicateInHistory]
+        );
+    }
+    #[test]
+    fn disabled_checks_are_skipped() {
+        let config = ImpressionConfig {
+            length_check: CheckMode::Off,
+            duplicate_check: CheckMode::Off,
+            ..Default::default()
+        };
+        let same = vec![IMPRESSION.to_string()];
+        assert!(check_impression(&config, "", &same, &same).is_empty());
+        assert!(check_impression(&config, IMPRESSION, &same, &same).is_empty());
+    }
+    #[test]
+    fn only_rejected_issues_block_saving() {
+        let config = ImpressionConfig::default();
+        assert_eq!(rejection_message(&config, &[]), None);
+        // Duplicates are only warned about by default
+        assert_eq!(
+            rejection_message(&config, &[ImpressionIssue::DuplicateInActivity]),
+            None
+        );
+        let issues = check_impression(&config, "", &[], &[]);
+        assert_eq!(
+            rejection_message(&config, &issues),
+            Some("Impression must be at least 30 characters long".to_string())
+        );
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{
+            attendances::{ActivityMember, AttendanceAction, AttendanceMode, AttendanceStatus},
+            groups::GroupPermission,
+        },
+        routers::activities::members::{
+            history::{create_history, record_history},
+            insert::{classify_member, InsertActivityMember, InsertMemberResult},
+            update::can_update_mode,
+        },
+        tests::helpers,
+    };
+    use bson::{doc, oid::ObjectId};
+    use std::collections::HashSet;
+
+    #[test]
+    fn members_already_enrolled_are_duplicates() {
+        let member = ObjectId::new();
+        let existing = HashSet::from([member]);
+        let mut seen = HashSet::new();
+        let result = classify_member(member, &existing, &mut seen, Some(&[]), true, &[]);
+        assert_eq!(result, InsertMemberResult::Duplicated);
+    }
+    #[test]
+    fn members_repeated_in_a_request_are_inserted_once() {
+        let member = ObjectId::new();
+        let existing = HashSet::new();
+        let mut seen = HashSet::new();
+        let results: Vec<InsertMemberResult> = (0..2)
+            .map(|_| classify_member(member, &existing, &mut seen, Some(&[]), true, &[]))
+            .collect();
+        assert_eq!(
+            results,
+            vec![InsertMemberResult::Inserted, InsertMemberResult::Duplicated]
+        );
+    }
+    #[test]
+    fn unknown_users_are_reported() {
+        let existing = HashSet::new();
+        let mut seen = HashSet::new();
+        let result = classify_member(ObjectId::new(), &existing, &mut seen, None, true, &[]);
+        assert_eq!(result, InsertMemberResult::NotFound);
+    }
+    #[test]
+    fn secretaries_only_enrol_their_own_class() {
+        let class = ObjectId::new();
+        let other = ObjectId::new();
+        let existing = HashSet::new();
+        let mut seen = HashSet::new();
+        let own = classify_member(
+            ObjectId::new(),
+            &existing,
+            &mut seen,
+            Some(&[class]),
+            false,
+            &[class],
+        );
+        assert_eq!(own, InsertMemberResult::Inserted);
+        let foreign = classify_member(
+            ObjectId::new(),
+            &existing,
+            &mut seen,
+            Some(&[other]),
+            false,
+            &[class],
+        );
+        assert_eq!(foreign, InsertMemberResult::Forbidden);
+        // Managers are not limited to classes
+        let managed = classify_member(
+            ObjectId::new(),
+            &existing,
+            &mut seen,
+            Some(&[other]),
+            true,
+            &[],
+        );
+        assert_eq!(managed, InsertMemberResult::Inserted);
+    }
+    #[test]
+    fn single_inserts_read_override_from_the_body() {
+        let id = ObjectId::new();
+        let body = format!(
+            r#"{{"_id":"{}","status":"effective","impression":null,"duration":12,"mode":"on-campus","history":null,"images":null,"imageSize":null,"override":true}}"#,
+            id.to_hex()
+        );
+        let insert: InsertActivityMember = serde_json::from_str(&body).unwrap();
+        assert_eq!(insert.member._id, id);
+        assert_eq!(insert.member.duration, 12.0);
+        assert_eq!(insert.override_rules, Some(true));
+    }
+    #[test]
+    fn reports_are_serialized_in_kebab_case() {
+        assert_eq!(
+            serde_json::to_string(&InsertMemberResult::NotFound).unwrap(),
+            "\"not-found\""
+        );
+    }
+
+    #[test]
+    fn history_snapshots_the_member() {
+        let actor = ObjectId::new();
+        let member = ActivityMember {
+            impression: Some("Planted trees".to_string()),
+            duration: 2.0,
+            mode: AttendanceMode::OffCampus,
+            ..helpers::member(ObjectId::new(), AttendanceStatus::Pending)
+        };
+        let entry = create_history(&member, actor, AttendanceAction::Mode);
+        assert_eq!(entry.actor, actor);
+        assert_eq!(entry.result, AttendanceStatus::Pending);
+        assert_eq!(entry.mode, Some(AttendanceMode::OffCampus));
+        assert_eq!(entry.action, Some(AttendanceAction::Mode));
+        assert_eq!(entry.impression, "Planted trees");
+        assert_eq!(entry.duration, 2.0);
+        assert!(entry.reason.is_none() && entry.overridden.is_none());
+    }
+    #[test]
+    fn members_change_the_mode_of_their_drafts_only() {
+        let student = [GroupPermission::Student];
+        assert!(can_update_mode(&student, true, &AttendanceStatus::Draft));
+        assert!(!can_update_mode(&student, true, &AttendanceStatus::Pending));
+        assert!(!can_update_mode(&student, false, &AttendanceStatus::Draft));
+        let auditor = [GroupPermission::Auditor];
+        assert!(can_update_mode(&auditor, false, &AttendanceStatus::Pending));
+    }
+    #[test]
+    fn history_entries_are_pushed() {
+        let member = helpers::member(ObjectId::new(), AttendanceStatus::Draft);
+        let entry = create_history(&member, ObjectId::new(), AttendanceAction::Mode);
+        let update = record_history(doc! {"members.$.mode": "on-campus"}, &member, &entry).unwrap();
+        assert_eq!(
+            update,
+            doc! {
+                "$set": {"members.$.mode": "on-campus"},
+                "$push": {"members.$.history": bson::to_bson(&entry).unwrap()},
+            }
+        );
+        let legacy = ActivityMember {
+            history: None,
+            ..member
+        };
+        let update = record_history(doc! {}, &legacy, &entry).unwrap();
+        assert_eq!(
+            update,
+            doc! {"$set": {"members.$.history": [bson::to_bson(&entry).unwrap()]}}
+        );
+    }
+}
@@ -1,2 +1,16 @@
 mod apis;
+mod attendances;
 mod auth;
+mod certificates;
+mod cursor;
+mod dates;
+mod exports;
+#[cfg(test)]
+mod helpers;
+mod images;
+mod impressions;
+mod members;
+mod requirements;
+mod reviews;
+mod storage;
+mod time;
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::groups::{Group, GroupType},
+        routers::users::time::UserActivityTime,
+        utils::{
+            config::RequirementProfile,
+            requirements::{calculate_progress, find_requirement_profile},
+        },
+    };
+    use bson::oid::ObjectId;
+
+    fn class(name: &str) -> Group {
+        Group {
+            _id: ObjectId::new(),
+            name: name.to_string(),
+            description: None,
+            permissions: vec![],
+            group_type: GroupType::Class,
+        }
+    }
+
+    fn profile(
+        name: &str,
+        classes: Option<Vec<String>>,
+        grade: Option<&str>,
+    ) -> RequirementProfile {
+        RequirementProfile {
+            name: name.to_string(),
+            classes,
+            grade: grade.map(|grade| grade.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn most_specific_profile_applies() {
+        let senior = class("2023级1班");
+        let junior = class("2024级3班");
+        let profiles = vec![
+            profile("class", Some(vec![senior._id.to_hex()]), None),
+            profile("default", None, None),
+            profile("grade", None, Some("2023")),
+        ];
+        let name = |group: &Group| {
+            find_requirement_profile(&profiles, std::slice::from_ref(group))
+                .map(|profile| profile.name.clone())
+        };
+        assert_eq!(name(&senior), Some("class".to_string()));
+        assert_eq!(name(&class("2023级2班")), Some("grade".to_string()));
+        assert_eq!(name(&junior), Some("default".to_string()));
+        assert!(find_requirement_profile(&profiles[..1], &[junior]).is_none());
+    }
+    #[test]
+    fn progress_reports_shortfall() {
+        let profile = RequirementProfile {
+            on_campus: 10.0,
+            off_campus: 5.0,
+            social_practice: 8.0,
+            total: 30.0,
+            ..Default::default()
+        };
+        let time = UserActivityTime {
+            on_campus: 12.0,
+            off_campus: 3.5,
+            social_practice: 8.0,
+            total: 23.5,
+            ..Default::default()
+        };
+        let progress = calculate_progress(Some(&profile), &time);
+        assert_eq!(progress.profile, Some("default".to_string()));
+        assert_eq!(progress.on_campus.shortfall, 0.0);
+        assert_eq!(progress.off_campus.shortfall, 1.5);
+        assert_eq!(progress.social_practice.shortfall, 0.0);
+        assert_eq!(progress.total.shortfall, 6.5);
+        assert!(!progress.eligible);
+        let done = UserActivityTime {
+            total: 30.0,
+            off_campus: 5.0,
+            ..time
+        };
+        assert!(calculate_progress(Some(&profile), &done).eligible);
+    }
+    #[test]
+    fn no_profile_means_no_requirement() {
+        let progress = calculate_progress(None, &UserActivityTime::default());
+        assert_eq!(progress.profile, None);
+        assert!(progress.eligible);
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::activities::ActivityType, routers::reviews::review_pipeline,
+        utils::dates::DateRange,
+    };
+    use bson::{doc, oid::ObjectId, Document};
+    use chrono::FixedOffset;
+
+    fn timezone() -> FixedOffset {
+        FixedOffset::east_opt(8 * 3600).unwrap()
+    }
+
+    fn page(pipeline: &[Document]) -> Document {
+        pipeline
+            .last()
+            .unwrap()
+            .get_document("$facet")
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn queue_is_paged_in_the_query() {
+        let pipeline = review_pipeline(None, None, &DateRange::default(), timezone(), 3, 10);
+        assert_eq!(
+            page(&pipeline),
+            doc! {
+                "items": [{"$skip": 20_i64}, {"$limit": 10_i64}],
+                "size": [{"$count": "size"}],
+            }
+        );
+    }
+    #[test]
+    fn queue_is_filtered_by_date_in_the_query() {
+        let range = DateRange {
+            from: Some(1717200000),
+            to: Some(1717372800),
+        };
+        let all = review_pipeline(None, None, &DateRange::default(), timezone(), 1, 20);
+        let dated = review_pipeline(None, None, &range, timezone(), 1, 20);
+        let stages = range.stages(timezone());
+        assert_eq!(dated.len(), all.len() + stages.len());
+        // Activities are filtered by date before their members are unwound
+        assert_eq!(dated[1..1 + stages.len()], stages[..]);
+        assert_eq!(dated[1 + stages.len()], doc! {"$unwind": "$members"});
+    }
+    #[test]
+    fn queue_is_filtered_by_type_and_class() {
+        let class = ObjectId::new();
+        let pipeline = review_pipeline(
+            Some(class),
+            Some(&ActivityType::Social),
+            &DateRange::default(),
+            timezone(),
+            1,
+            20,
+        );
+        assert_eq!(
+            pipeline[0],
+            doc! {"$match": {"members.status": "pending", "type": "social"}}
+        );
+        assert!(pipeline.contains(&doc! {"$match": {"classes._id": class}}));
+    }
+    #[test]
+    fn queue_order_is_stable_within_an_activity() {
+        let pipeline = review_pipeline(None, None, &DateRange::default(), timezone(), 1, 20);
+        assert!(pipeline.contains(&doc! {"$sort": {"_id": 1, "members._id": 1}}));
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::storage::{
+        local::LocalStorage,
+        signature::{sign_object_key, verify_object_key},
+        Storage, StorageError,
+    };
+
+    const SECRET: &[u8] = b"secret";
+    const KEY: &str = "members/a/b/c.jpg";
+
+    #[test]
+    fn signature_round_trip() {
+        let signature = sign_object_key(SECRET, KEY, 1000);
+        assert!(verify_object_key(SECRET, KEY, 1000, &signature, 999).is_ok());
+        assert!(verify_object_key(SECRET, KEY, 1000, &signature, 1000).is_ok());
+    }
+    #[test]
+    fn signature_rejects_tampering() {
+        let signature = sign_object_key(SECRET, KEY, 1000);
+        assert!(verify_object_key(SECRET, KEY, 1000, &signature, 1001).is_err());
+        assert!(verify_object_key(SECRET, KEY, 2000, &signature, 999).is_err());
+        assert!(verify_object_key(SECRET, "members/a/b/d.jpg", 1000, &signature, 999).is_err());
+        assert!(verify_object_key(b"other", KEY, 1000, &signature, 999).is_err());
+        assert!(verify_object_key(SECRET, KEY, 1000, "not hex", 999).is_err());
+    }
+    #[tokio::test]
+    async fn local_storage_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(root.path());
+        storage.put(KEY, "image/jpeg", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(storage.get(KEY).await.unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            storage.get("members/missing.jpg").await,
+            Err(StorageError::NotFound)
+        );
+        assert_eq!(storage.get("../secret").await, Err(StorageError::NotFound));
+        assert_eq!(
+            storage.get("/etc/passwd").await,
+            Err(StorageError::NotFound)
+        );
+        storage.delete(KEY).await.unwrap();
+        assert!(storage.get(KEY).await.is_err());
+        assert!(storage.delete("../secret").await.is_err());
+    }
+}
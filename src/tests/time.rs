@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::routers::users::time::{signed_duration, UserActivityTime, UserActivityTimes};
+
+    #[test]
+    fn only_counted_statuses_are_added() {
+        let mut times = UserActivityTimes::default();
+        times.add("effective", "on-campus", "", 2.0);
+        times.add("pending", "off-campus", "", 3.0);
+        times.add("refused", "social-practice", "", 4.0);
+        times.add("draft", "on-campus", "", 5.0);
+        times.add("rejected", "on-campus", "", 6.0);
+        assert_eq!(times.effective.on_campus, 2.0);
+        assert_eq!(times.effective.total, 2.0);
+        assert_eq!(times.pending.off_campus, 3.0);
+        assert_eq!(times.pending.total, 3.0);
+        assert_eq!(times.refused.social_practice, 4.0);
+        assert_eq!(times.refused.total, 4.0);
+    }
+    #[test]
+    fn special_categories_are_broken_down() {
+        let mut times = UserActivityTimes::default();
+        times.add("effective", "on-campus", "", 10.0);
+        times.add("effective", "on-campus", "prize", 2.0);
+        times.add("effective", "off-campus", "import", 6.0);
+        times.add("effective", "on-campus", "deduction", 1.5);
+        times.add("effective", "on-campus", "deduction", -0.5);
+        let time = times.effective;
+        assert_eq!(time.special.prize, 2.0);
+        assert_eq!(time.special.historical, 6.0);
+        assert_eq!(time.special.deduction, -2.0);
+        assert_eq!(time.on_campus, 10.0);
+        assert_eq!(time.off_campus, 6.0);
+        assert_eq!(time.total, 16.0);
+    }
+    #[test]
+    fn times_are_merged() {
+        let mut times = UserActivityTimes::default();
+        times.add("effective", "on-campus", "prize", 2.0);
+        times.add("effective", "off-campus", "deduction", 1.0);
+        let mut total = UserActivityTime::default();
+        total.merge(&times.effective);
+        total.merge(&times.effective);
+        assert_eq!(total.on_campus, 4.0);
+        assert_eq!(total.off_campus, -2.0);
+        assert_eq!(total.special.prize, 4.0);
+        assert_eq!(total.total, 2.0);
+    }
+    #[test]
+    fn only_deductions_are_negative() {
+        assert_eq!(signed_duration("deduction", 1.5), -1.5);
+        assert_eq!(signed_duration("deduction", -1.5), -1.5);
+        assert_eq!(signed_duration("prize", 1.5), 1.5);
+        assert_eq!(signed_duration("", 1.5), 1.5);
+    }
+}
@@ -8,7 +8,7 @@ pub fn generate_aes256_key() -> String {
 
 pub fn read_aes256_key() -> String {
     let key = std::fs::read_to_string("aes.key");
-    if let Err(_) = key {
+    if key.is_err() {
         let key = generate_aes256_key();
         let _ = std::fs::write("aes.key", key.as_bytes());
         return key;
@@ -0,0 +1,93 @@
+use crate::{
+    models::{activities::ActivityType, attendances::AttendanceMode, groups::GroupPermission},
+    utils::config::{DurationConfig, DurationRule},
+};
+use axum::http::StatusCode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DurationError {
+    Negative,
+    TooLong(f64),
+    Granularity(f64),
+}
+
+impl DurationError {
+    pub fn describe(&self) -> String {
+        match self {
+            DurationError::Negative => "Duration cannot be negative".to_string(),
+            DurationError::TooLong(max) => format!("Duration cannot exceed {} hours", max),
+            DurationError::Granularity(step) => {
+                format!("Duration must be a multiple of {} hours", step)
+            }
+        }
+    }
+}
+
+/// The most specific rule for the activity type and mode: one naming both
+/// wins over one naming the type, which wins over one naming the mode.
+/// Special activities hold imported or deducted hours, so only rules naming
+/// the special type limit them.
+pub fn find_duration_rule<'a>(
+    config: &'a DurationConfig,
+    activity_type: &ActivityType,
+    mode: &AttendanceMode,
+) -> Option<&'a DurationRule> {
+    config
+        .rules
+        .iter()
+        .rev()
+        .filter(|rule| match &rule.activity_type {
+            Some(t) => t == activity_type,
+            None => activity_type != &ActivityType::Special,
+        })
+        .filter(|rule| rule.mode.as_ref().is_none_or(|m| m == mode))
+        .max_by_key(|rule| (rule.activity_type.is_some(), rule.mode.is_some()))
+}
+
+pub fn validate_duration(rule: Option<&DurationRule>, duration: f64) -> Result<(), DurationError> {
+    if duration < 0.0 {
+        return Err(DurationError::Negative);
+    }
+    let Some(rule) = rule else {
+        return Ok(());
+    };
+    if duration > rule.max {
+        return Err(DurationError::TooLong(rule.max));
+    }
+    if rule.step > 0.0 {
+        let steps = duration / rule.step;
+        if (steps - steps.round()).abs() > 1e-6 {
+            return Err(DurationError::Granularity(rule.step));
+        }
+    }
+    Ok(())
+}
+
+/// Applies the duration rules on behalf of a user, who may ask to override
+/// them if they are an Admin. Returns whether the rules were overridden, so
+/// the caller can record it in the member's history.
+pub fn check_duration(
+    config: &DurationConfig,
+    activity_type: &ActivityType,
+    mode: &AttendanceMode,
+    duration: f64,
+    override_rules: bool,
+    perms: &[GroupPermission],
+) -> Result<bool, (StatusCode, String)> {
+    if override_rules && !perms.contains(&GroupPermission::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only admins may override duration rules".to_string(),
+        ));
+    }
+    let rule = find_duration_rule(config, activity_type, mode);
+    match validate_duration(rule, duration) {
+        Ok(()) => Ok(false),
+        // Negative durations are never meaningful, even for admins
+        Err(DurationError::Negative) => {
+            Err((StatusCode::BAD_REQUEST, DurationError::Negative.describe()))
+        }
+        Err(_) if override_rules => Ok(true),
+        Err(e) => Err((StatusCode::BAD_REQUEST, e.describe())),
+    }
+}
@@ -0,0 +1,54 @@
+use crate::models::{attendances::AttendanceStatus, groups::GroupPermission};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransitionRole {
+    Member,
+    Auditor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TransitionError {
+    Invalid,
+    Forbidden,
+}
+
+/// Roles the user holds towards a member: the member themself and/or an auditor.
+pub fn transition_roles(is_member: bool, perms: &[GroupPermission]) -> Vec<TransitionRole> {
+    let mut roles = vec![];
+    if is_member {
+        roles.push(TransitionRole::Member);
+    }
+    if perms.contains(&GroupPermission::Auditor) || perms.contains(&GroupPermission::Admin) {
+        roles.push(TransitionRole::Auditor);
+    }
+    roles
+}
+
+/// The role allowed to move an attendance from `from` to `to`, if the transition exists.
+///
+/// Members edit and submit drafts and resubmit rejected attendances; auditors
+/// review pending ones. Staying in the same state is how durations are edited.
+pub fn transition_role(from: &AttendanceStatus, to: &AttendanceStatus) -> Option<TransitionRole> {
+    use AttendanceStatus::*;
+    match (from, to) {
+        (Draft, Draft) | (Draft, Pending) | (Rejected, Rejected) | (Rejected, Pending) => {
+            Some(TransitionRole::Member)
+        }
+        (Pending, Pending) | (Pending, Effective) | (Pending, Refused) | (Pending, Rejected) => {
+            Some(TransitionRole::Auditor)
+        }
+        _ => None,
+    }
+}
+
+pub fn validate_transition(
+    from: &AttendanceStatus,
+    to: &AttendanceStatus,
+    roles: &[TransitionRole],
+) -> Result<(), TransitionError> {
+    match transition_role(from, to) {
+        None => Err(TransitionError::Invalid),
+        Some(role) if roles.contains(&role) => Ok(()),
+        Some(_) => Err(TransitionError::Forbidden),
+    }
+}
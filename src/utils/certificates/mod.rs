@@ -0,0 +1,57 @@
+pub mod pdf;
+
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Crockford's base 32, which leaves out letters easily mistaken for digits.
+const CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const CODE_LENGTH: usize = 10;
+/// Codes drawn before giving up on finding an unused one.
+pub const CODE_ATTEMPTS: usize = 3;
+/// MongoDB's error code for a write that breaks a unique index.
+const DUPLICATE_KEY: i32 = 11000;
+
+/// A random code like `7K2QX-M9C4D` printed on a certificate.
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    let mut code = String::with_capacity(CODE_LENGTH + 1);
+    for i in 0..CODE_LENGTH {
+        if i == CODE_LENGTH / 2 {
+            code.push('-');
+        }
+        code.push(CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char);
+    }
+    code
+}
+
+/// Whether an insert failed because the code is already taken.
+pub fn is_duplicate_key(error: &Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY
+    )
+}
+
+/// Uppercases a code typed by hand and maps the letters Crockford's base 32
+/// reads as digits, so `7k2qx m9c4o` finds `7K2QX-M9C40`.
+pub fn normalize_code(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+    if code.len() != CODE_LENGTH {
+        return code;
+    }
+    format!("{}-{}", &code[..CODE_LENGTH / 2], &code[CODE_LENGTH / 2..])
+}
+
+/// SHA-256 of an issued document, so a copy can be compared with the record.
+pub fn hash_document(document: &[u8]) -> String {
+    hex::encode(Sha256::digest(document))
+}
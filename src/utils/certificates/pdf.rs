@@ -0,0 +1,208 @@
+use crate::routers::users::time::UserActivityTime;
+use printpdf::{IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const ROW_HEIGHT: f32 = 6.5;
+/// Activity names are cut to fit their column.
+const MAX_NAME_LENGTH: usize = 28;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateActivity {
+    pub date: String,
+    pub name: String,
+    pub mode: String,
+    pub duration: f64,
+}
+
+/// Everything printed on a certificate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateContent {
+    pub issuer: String,
+    pub name: String,
+    pub id: String,
+    pub class: String,
+    /// The dates the hours were counted between, or a note that all are.
+    pub period: String,
+    pub issued: String,
+    pub code: String,
+    pub time: UserActivityTime,
+    pub activities: Vec<CertificateActivity>,
+}
+
+fn mode_label(mode: &str) -> &str {
+    match mode {
+        "on-campus" => "校内 On campus",
+        "off-campus" => "校外 Off campus",
+        "social-practice" => "社会实践 Social practice",
+        mode => mode,
+    }
+}
+
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let mut text: String = text.chars().take(length - 1).collect();
+    text.push('…');
+    text
+}
+
+struct Writer<'a> {
+    document: &'a printpdf::PdfDocumentReference,
+    font: IndirectFontRef,
+    layer: PdfLayerReference,
+    content: &'a CertificateContent,
+    y: f32,
+}
+
+impl Writer<'_> {
+    fn text(&self, text: &str, size: f32, x: f32) {
+        self.layer
+            .use_text(text, size, Mm(x), Mm(self.y), &self.font);
+    }
+
+    fn rule(&self) {
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(self.y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(self.y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    fn footer(&self) {
+        let footer = format!(
+            "验证码 Verification code: {}    签发 Issued: {}    {}",
+            self.content.code, self.content.issued, self.content.issuer
+        );
+        self.layer
+            .use_text(footer, 9.0, Mm(MARGIN), Mm(12.0), &self.font);
+    }
+
+    /// Starts a new page when fewer than `height` millimetres are left.
+    fn reserve(&mut self, height: f32) {
+        if self.y - height >= MARGIN + 10.0 {
+            return;
+        }
+        let (page, layer) = self
+            .document
+            .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        self.layer = self.document.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.footer();
+    }
+
+    fn activity_header(&mut self) {
+        self.reserve(ROW_HEIGHT * 2.0);
+        self.text("日期 Date", 10.0, MARGIN);
+        self.text("活动 Activity", 10.0, MARGIN + 25.0);
+        self.text("类型 Mode", 10.0, MARGIN + 110.0);
+        self.text("时长 Hours", 10.0, MARGIN + 150.0);
+        self.y -= 2.0;
+        self.rule();
+        self.y -= ROW_HEIGHT - 1.0;
+    }
+}
+
+/// Renders a certificate as a PDF. `font` must cover the CJK characters in
+/// names and activities; only the glyphs used are embedded.
+pub fn render_certificate(font: &[u8], content: &CertificateContent) -> Result<Vec<u8>, String> {
+    let (document, page, layer) = PdfDocument::new(
+        format!("{} {}", content.name, content.code),
+        Mm(PAGE_WIDTH),
+        Mm(PAGE_HEIGHT),
+        "Layer 1",
+    );
+    let font = document.add_external_font(font);
+    if let Err(e) = font {
+        return Err(format!("Failed to load certificate font: {}", e));
+    }
+    let layer = document.get_page(page).get_layer(layer);
+    let mut writer = Writer {
+        document: &document,
+        font: font.unwrap(),
+        layer,
+        content,
+        y: PAGE_HEIGHT - MARGIN - 10.0,
+    };
+    writer.footer();
+
+    writer.text("志愿服务时长证明", 22.0, MARGIN);
+    writer.y -= 9.0;
+    writer.text("Certificate of Volunteer Hours", 14.0, MARGIN);
+    writer.y -= 14.0;
+
+    for (label, value) in [
+        ("姓名 Name", content.name.as_str()),
+        ("学号 Number", content.id.as_str()),
+        ("班级 Class", content.class.as_str()),
+        ("统计期间 Period", content.period.as_str()),
+    ] {
+        writer.text(label, 11.0, MARGIN);
+        writer.text(value, 11.0, MARGIN + 40.0);
+        writer.y -= ROW_HEIGHT + 1.0;
+    }
+    writer.y -= 4.0;
+
+    let time = &content.time;
+    let mut totals = vec![
+        ("校内 On campus", time.on_campus),
+        ("校外 Off campus", time.off_campus),
+        ("社会实践 Social practice", time.social_practice),
+    ];
+    for (label, hours) in [
+        ("获奖 Prize", time.special.prize),
+        ("社团 Club", time.special.club),
+        ("历史记录 Historical", time.special.historical),
+        ("扣除 Deduction", time.special.deduction),
+        ("其他 Other", time.special.other),
+    ] {
+        // Only list special categories the student actually has
+        if hours != 0.0 {
+            totals.push((label, hours));
+        }
+    }
+    totals.push(("合计 Total", time.total));
+    writer.text("时长 Hours", 12.0, MARGIN);
+    writer.y -= 2.0;
+    writer.rule();
+    writer.y -= ROW_HEIGHT - 1.0;
+    for (label, hours) in totals {
+        writer.text(label, 11.0, MARGIN);
+        writer.text(&format!("{:.1}", hours), 11.0, MARGIN + 60.0);
+        writer.y -= ROW_HEIGHT;
+    }
+    writer.y -= 8.0;
+
+    writer.reserve(ROW_HEIGHT * 3.0);
+    writer.text("活动记录 Activities", 12.0, MARGIN);
+    writer.y -= ROW_HEIGHT + 1.0;
+    writer.activity_header();
+    if content.activities.is_empty() {
+        writer.text("无 None", 10.0, MARGIN);
+    }
+    for activity in content.activities.iter() {
+        if writer.y - ROW_HEIGHT < MARGIN + 10.0 {
+            writer.reserve(ROW_HEIGHT * 2.0);
+            writer.activity_header();
+        }
+        writer.text(&activity.date, 10.0, MARGIN);
+        writer.text(
+            &truncate(&activity.name, MAX_NAME_LENGTH),
+            10.0,
+            MARGIN + 25.0,
+        );
+        writer.text(mode_label(&activity.mode), 10.0, MARGIN + 110.0);
+        writer.text(&format!("{:.1}", activity.duration), 10.0, MARGIN + 150.0);
+        writer.y -= ROW_HEIGHT;
+    }
+
+    let bytes = document.save_to_bytes();
+    if let Err(e) = bytes {
+        return Err(format!("Failed to render certificate: {}", e));
+    }
+    Ok(bytes.unwrap())
+}
@@ -1,14 +1,205 @@
 use std::env;
 
+use crate::models::{activities::ActivityType, attendances::AttendanceMode};
 use serde::{Deserialize, Serialize};
 use tokio::fs::{read, write};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum StorageConfig {
+    Local {
+        path: String,
+    },
+    B2 {
+        key_id: String,
+        key: String,
+        bucket_id: String,
+        bucket_name: String,
+    },
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig::Local {
+            path: "storage".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageConfig {
+    /// Largest accepted upload, in bytes.
+    pub max_file_size: u64,
+    /// Most images a single activity member may attach.
+    pub max_member_images: usize,
+    /// Largest total size of a member's original images, in bytes.
+    pub max_member_size: u64,
+    /// Longest edge of generated thumbnails, in pixels.
+    pub thumbnail_size: u32,
+    /// Longest edge of generated previews, in pixels.
+    pub preview_size: u32,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            max_file_size: 10 * 1024 * 1024,
+            max_member_images: 9,
+            max_member_size: 40 * 1024 * 1024,
+            thumbnail_size: 320,
+            preview_size: 1600,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckMode {
+    Off,
+    Warn,
+    Reject,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImpressionConfig {
+    /// Shortest accepted impression, see `impression_length`.
+    pub min_length: usize,
+    pub length_check: CheckMode,
+    /// Similarity from 0 to 1 at which two impressions count as duplicates.
+    pub max_similarity: f64,
+    pub duplicate_check: CheckMode,
+}
+
+impl Default for ImpressionConfig {
+    fn default() -> Self {
+        ImpressionConfig {
+            min_length: 30,
+            length_check: CheckMode::Reject,
+            max_similarity: 0.9,
+            duplicate_check: CheckMode::Warn,
+        }
+    }
+}
+
+/// Duration limits for members of activities of a type and/or in a mode.
+/// Leaving either out makes the rule apply to all of them, except that a
+/// rule without a type does not apply to special activities.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DurationRule {
+    #[serde(rename = "type")]
+    pub activity_type: Option<ActivityType>,
+    pub mode: Option<AttendanceMode>,
+    /// Longest duration a member may be credited, in hours.
+    pub max: f64,
+    /// Durations must be a multiple of this, in hours. Zero allows any value.
+    pub step: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DurationConfig {
+    pub rules: Vec<DurationRule>,
+}
+
+impl Default for DurationConfig {
+    fn default() -> Self {
+        DurationConfig {
+            rules: vec![
+                DurationRule {
+                    activity_type: None,
+                    mode: None,
+                    max: 10.0,
+                    step: 0.5,
+                },
+                DurationRule {
+                    activity_type: None,
+                    mode: Some(AttendanceMode::SocialPractice),
+                    max: 48.0,
+                    step: 0.5,
+                },
+            ],
+        }
+    }
+}
+
+/// Minimum hours per category a student must reach, e.g. to graduate.
+/// A profile can be limited to classes by ID and/or to a grade, matched as
+/// a prefix of the class name. The most specific matching profile applies.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RequirementProfile {
+    pub name: String,
+    pub classes: Option<Vec<String>>,
+    pub grade: Option<String>,
+    pub on_campus: f64,
+    pub off_campus: f64,
+    pub social_practice: f64,
+    pub total: f64,
+}
+
+impl Default for RequirementProfile {
+    fn default() -> Self {
+        RequirementProfile {
+            name: "default".to_string(),
+            classes: None,
+            grade: None,
+            on_campus: 30.0,
+            off_campus: 15.0,
+            social_practice: 18.0,
+            total: 0.0,
+        }
+    }
+}
+
+/// A school term, from its first to its last day (both `YYYY-MM-DD`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportConfig {
+    /// Hours finished export files are kept before they are deleted.
+    pub retention: u64,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig { retention: 72 }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CertificateConfig {
+    /// Name printed as the issuer of certificates.
+    pub issuer: String,
+    /// TrueType or OpenType font with CJK glyphs, embedded into certificates.
+    pub font: String,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        CertificateConfig {
+            issuer: "ZVMS".to_string(),
+            font: "fonts/NotoSansSC-Regular.ttf".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Config {
     pub server: String,
     pub database: String,
     pub timezone: String,
     pub port: u16,
+    pub storage: Option<StorageConfig>,
+    pub images: Option<ImageConfig>,
+    pub impressions: Option<ImpressionConfig>,
+    pub durations: Option<DurationConfig>,
+    pub requirements: Option<Vec<RequirementProfile>>,
+    pub terms: Option<Vec<Term>>,
+    pub exports: Option<ExportConfig>,
+    pub certificates: Option<CertificateConfig>,
 }
 
 pub async fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
@@ -30,6 +221,14 @@ pub async fn init_config() -> Result<(), Box<dyn std::error::Error>> {
         database: "zvms".to_string(),
         timezone: "0".to_string(),
         port: 8080,
+        storage: Some(StorageConfig::default()),
+        images: Some(ImageConfig::default()),
+        impressions: Some(ImpressionConfig::default()),
+        durations: Some(DurationConfig::default()),
+        requirements: Some(vec![RequirementProfile::default()]),
+        terms: Some(vec![]),
+        exports: Some(ExportConfig::default()),
+        certificates: Some(CertificateConfig::default()),
     };
     save_config(config).await?;
     Ok(())
@@ -48,7 +247,7 @@ pub async fn load_or_init_config() -> Result<Config, Box<dyn std::error::Error>>
 
 pub fn load_config_sync() -> Result<Config, Box<dyn std::error::Error>> {
     let config = std::fs::read("config.json");
-    if let Err(_) = config {
+    if config.is_err() {
         return Err("Failed to read config file".into());
     }
     let config = config.unwrap();
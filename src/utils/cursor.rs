@@ -0,0 +1,20 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bson::oid::ObjectId;
+
+/// Encodes the `_id` of the last document of a page as an opaque cursor.
+pub fn encode_cursor(id: &ObjectId) -> String {
+    URL_SAFE_NO_PAD.encode(id.bytes())
+}
+
+/// Decodes a cursor produced by `encode_cursor` back into the `_id` to continue after.
+pub fn decode_cursor(cursor: &str) -> Result<ObjectId, String> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor);
+    if bytes.is_err() {
+        return Err("Invalid cursor".to_string());
+    }
+    let bytes: Result<[u8; 12], _> = bytes.unwrap().try_into();
+    if bytes.is_err() {
+        return Err("Invalid cursor".to_string());
+    }
+    Ok(ObjectId::from_bytes(bytes.unwrap()))
+}
@@ -0,0 +1,94 @@
+use crate::utils::config::Config;
+use bson::{doc, Document};
+use chrono::{FixedOffset, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Filters activities by date. `term` names a term from the config and is
+/// narrowed further by `from` and `to`, which are UNIX timestamps.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct DateRangeQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+pub fn config_timezone(config: &Config) -> FixedOffset {
+    let hours: i32 = config.timezone.parse().unwrap_or(0);
+    FixedOffset::east_opt(hours * 3600).unwrap_or(FixedOffset::east_opt(0).unwrap())
+}
+
+fn parse_day(day: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", day))
+}
+
+impl DateRangeQuery {
+    pub fn resolve(&self, config: &Config) -> Result<DateRange, String> {
+        let mut range = DateRange {
+            from: self.from,
+            to: self.to,
+        };
+        if let Some(name) = &self.term {
+            let terms = config.terms.clone().unwrap_or_default();
+            let term = terms.iter().find(|term| &term.name == name);
+            if term.is_none() {
+                return Err(format!("Unknown term: {}", name));
+            }
+            let term = term.unwrap();
+            let timezone = config_timezone(config);
+            let start = parse_day(&term.start)?.and_hms_opt(0, 0, 0).unwrap();
+            let end = parse_day(&term.end)?.and_hms_opt(23, 59, 59).unwrap();
+            let start = timezone.from_local_datetime(&start).unwrap().timestamp() as u64;
+            let end = timezone.from_local_datetime(&end).unwrap().timestamp() as u64;
+            range.from = Some(range.from.map_or(start, |from| from.max(start)));
+            range.to = Some(range.to.map_or(end, |to| to.min(end)));
+        }
+        Ok(range)
+    }
+}
+
+impl DateRange {
+    /// Aggregation stages keeping activities dated within the range.
+    ///
+    /// Dates are stored either as UNIX timestamps or as strings, in RFC 3339 or
+    /// as `%Y-%m-%d %H:%M:%S` in the configured timezone, so they are
+    /// converted to timestamps before comparing.
+    pub fn stages(&self, timezone: FixedOffset) -> Vec<Document> {
+        if self.from.is_none() && self.to.is_none() {
+            return vec![];
+        }
+        let offset = timezone.local_minus_utc() / 60;
+        let timezone = format!("{:+03}:{:02}", offset / 60, (offset % 60).abs());
+        let mut bounds = doc! {};
+        if let Some(from) = self.from {
+            bounds.insert("$gte", from as i64);
+        }
+        if let Some(to) = self.to {
+            bounds.insert("$lte", to as i64);
+        }
+        vec![
+            doc! {"$addFields": {"dateSeconds": {"$cond": [
+                {"$eq": [{"$type": "$date"}, "string"]},
+                {"$divide": [
+                    {"$toLong": {"$dateFromString": {
+                        "dateString": "$date",
+                        "format": "%Y-%m-%d %H:%M:%S",
+                        "timezone": timezone,
+                        "onError": {"$dateFromString": {
+                            "dateString": "$date",
+                            "onError": null,
+                        }},
+                    }}},
+                    1000,
+                ]},
+                "$date",
+            ]}}},
+            doc! {"$match": {"dateSeconds": bounds}},
+        ]
+    }
+}
@@ -1,151 +1,298 @@
 use crate::{
-    models::{activities::Activity, users::User},
-    routers::users::time::UserActivityTime,
+    models::users::User,
+    routers::users::time::{UserActivityTime, UserActivityTimes},
+    utils::dates::DateRange,
 };
-use bson::{doc, from_document};
+use bson::{doc, oid::ObjectId, Bson, Document};
+use chrono::FixedOffset;
 use futures::stream::TryStreamExt;
 use mongodb::{Collection, Database};
 use polars::{
-    df,
     frame::DataFrame,
     io::{csv::CsvWriter, SerWriter},
     prelude::NamedFrom,
     series::Series,
 };
-use std::{fs::File, sync::Arc};
-use tokio::sync::Mutex;
-
-pub async fn export_to_dataframe(db: Arc<Mutex<Database>>) -> Result<DataFrame, String> {
-    let db = db.lock().await;
-    let mut df = df!(
-        "_id" => &["".to_string()],
-        "id" => &["0".to_string()],
-        "name" => &["Example".to_string()],
-        "class" => &["".to_string()],
-        "on_campus" => &[0.0],
-        "off_campus" => &[0.0],
-        "social_practice" => &[0.0],
-        "total" => &[0.0]
-    )
-    .unwrap();
-
-    println!("Start to export data");
-
-    let users_collection: Collection<User> = db.collection("users");
-    let activities_collection: Collection<Activity> = db.collection("activities");
-
-    let mut users = users_collection.find(doc! {}, None).await.unwrap();
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs::File, io::Write, sync::Arc};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::info;
 
-    let mut count = 0;
+/// Effective hours of one user for a mode and special activity category.
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ExportTime {
+    #[serde(default)]
+    pub mode: String,
+    /// Missing or null for activities outside a special category.
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub duration: Bson,
+}
 
-    while let Some(doc) = users.try_next().await.unwrap() {
-        count += 1;
-        if count % 100 == 99 {
-            return Ok(df);
+/// One user as produced by [`export_pipeline`].
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ExportRow {
+    pub _id: ObjectId,
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub classes: Vec<String>,
+    #[serde(default)]
+    pub times: Vec<ExportTime>,
+}
+
+impl ExportRow {
+    pub fn class(&self) -> String {
+        self.classes.join(", ")
+    }
+
+    pub fn time(&self) -> UserActivityTime {
+        let mut times = UserActivityTimes::default();
+        for time in self.times.iter() {
+            let duration = match time.duration {
+                Bson::Double(duration) => duration,
+                Bson::Int32(duration) => duration as f64,
+                Bson::Int64(duration) => duration as f64,
+                _ => continue,
+            };
+            let category = time.category.as_deref().unwrap_or_default();
+            times.add("effective", &time.mode, category, duration);
         }
-        println!("Processing {}'s data", doc.name);
-        let pipeline = vec![
-            doc! {
-                "$match": {
-                    "$or": [
-                        { "members._id": doc._id.clone() },
-                        { "members._id": doc._id.to_hex() }
-                    ]
-                }
-            },
-            doc! {
-                "$unwind": "$members"
-            },
-            doc! {
-                "$match": {
-                    "$or": [
-                        { "members._id": doc._id.clone() },
-                        { "members._id": doc._id.to_hex() }
-                    ]
-                }
-            },
-            doc! {
-                "$group": {
-                    "_id": "$members.mode",
-                    "totalDuration": { "$sum": "$members.duration" }
-                }
-            },
-            doc! {
-                "$group": {
-                    "_id": null,
-                    "on_campus": {
-                        "$sum": {
-                            "$cond": [{ "$eq": ["$_id", "on-campus"] }, "$totalDuration", 0.0]
-                        }
-                    },
-                    "off_campus": {
-                        "$sum": {
-                            "$cond": [{ "$eq": ["$_id", "off-campus"] }, "$totalDuration", 0.0]
-                        }
-                    },
-                    "social_practice": {
-                        "$sum": {
-                            "$cond": [{ "$eq": ["$_id", "social-practice"] }, "$totalDuration", 0.0]
-                        }
-                    },
-                    "total": { "$sum": "$totalDuration" }
-                }
-            },
-            doc! {
-                "$project": {
-                    "_id": 0,
-                    "on_campus": 1,
-                    "off_campus": 1,
-                    "social_practice": 1,
-                    "total": 1
-                }
+        times.effective
+    }
+}
+
+/// Sums effective hours per member, mode and category over the activities in
+/// `range`, then joins every user with their class names. Users without any
+/// activity are added through `$unionWith` so they are exported with zeros.
+/// Unless `classes` is empty, only users in those groups are kept.
+pub fn export_pipeline(
+    classes: &[ObjectId],
+    range: &DateRange,
+    timezone: FixedOffset,
+) -> Vec<Document> {
+    let mut users = vec![doc! {"$project": {"_id": {"user": "$_id"}}}];
+    if !classes.is_empty() {
+        users.insert(0, doc! {"$match": {"group": {"$in": classes}}});
+    }
+    let mut pipeline = range.stages(timezone);
+    pipeline.extend([
+        doc! {"$unwind": "$members"},
+        doc! {"$match": {"members.status": "effective"}},
+        doc! {"$group": {
+            "_id": {
+                "user": "$members._id",
+                "mode": "$members.mode",
+                "category": "$category",
             },
-        ];
-        let cursor = activities_collection.aggregate(pipeline, None).await;
-        println!("Got cursor");
-        if let Err(_) = cursor {
-            return Err("Failed to get cursor".to_string());
+            "duration": {"$sum": "$members.duration"},
+        }},
+        doc! {"$unionWith": {
+            "coll": "users",
+            "pipeline": users,
+        }},
+        doc! {"$group": {
+            "_id": "$_id.user",
+            "times": {"$push": {
+                "mode": "$_id.mode",
+                "category": "$_id.category",
+                "duration": "$duration",
+            }},
+        }},
+        doc! {"$lookup": {
+            "from": "users",
+            "localField": "_id",
+            "foreignField": "_id",
+            "as": "user",
+        }},
+        doc! {"$unwind": "$user"},
+    ]);
+    if !classes.is_empty() {
+        pipeline.push(doc! {"$match": {"user.group": {"$in": classes}}});
+    }
+    pipeline.extend([
+        doc! {"$lookup": {
+            "from": "groups",
+            "localField": "user.group",
+            "foreignField": "_id",
+            "as": "groups",
+        }},
+        doc! {"$project": {
+            "_id": 1,
+            "id": "$user.id",
+            "name": "$user.name",
+            "classes": {"$map": {
+                "input": {"$filter": {
+                    "input": "$groups",
+                    "cond": {"$eq": ["$$this.type", "class"]},
+                }},
+                "in": "$$this.name",
+            }},
+            "times": 1,
+        }},
+        doc! {"$sort": {"id": 1}},
+    ]);
+    pipeline
+}
+
+/// Column buffers for the activity time export.
+#[derive(Debug, Default)]
+pub struct ExportColumns {
+    _id: Vec<String>,
+    id: Vec<String>,
+    name: Vec<String>,
+    class: Vec<String>,
+    on_campus: Vec<f64>,
+    off_campus: Vec<f64>,
+    social_practice: Vec<f64>,
+    prize: Vec<f64>,
+    club: Vec<f64>,
+    historical: Vec<f64>,
+    deduction: Vec<f64>,
+    other: Vec<f64>,
+    total: Vec<f64>,
+}
+
+impl ExportColumns {
+    pub fn push(
+        &mut self,
+        _id: String,
+        id: String,
+        name: String,
+        class: String,
+        time: &UserActivityTime,
+    ) {
+        self._id.push(_id);
+        self.id.push(id);
+        self.name.push(name);
+        self.class.push(class);
+        self.on_campus.push(time.on_campus);
+        self.off_campus.push(time.off_campus);
+        self.social_practice.push(time.social_practice);
+        self.prize.push(time.special.prize);
+        self.club.push(time.special.club);
+        self.historical.push(time.special.historical);
+        self.deduction.push(time.special.deduction);
+        self.other.push(time.special.other);
+        self.total.push(time.total);
+    }
+
+    pub fn push_row(&mut self, row: &ExportRow) {
+        self.push(
+            row._id.to_hex(),
+            row.id.clone(),
+            row.name.clone(),
+            row.class(),
+            &row.time(),
+        );
+    }
+
+    pub fn into_dataframe(self) -> Result<DataFrame, String> {
+        let df = DataFrame::new(vec![
+            Series::new("_id", self._id),
+            Series::new("id", self.id),
+            Series::new("name", self.name),
+            Series::new("class", self.class),
+            Series::new("on_campus", self.on_campus),
+            Series::new("off_campus", self.off_campus),
+            Series::new("social_practice", self.social_practice),
+            Series::new("prize", self.prize),
+            Series::new("club", self.club),
+            Series::new("historical", self.historical),
+            Series::new("deduction", self.deduction),
+            Series::new("other", self.other),
+            Series::new("total", self.total),
+        ]);
+        if df.is_err() {
+            return Err("Failed to create DataFrame".to_string());
+        }
+        Ok(df.unwrap())
+    }
+}
+
+/// Exports every user, or only those in `classes` followed by a total row
+/// per class.
+pub async fn export_to_dataframe(
+    db: Arc<Mutex<Database>>,
+    classes: &[ObjectId],
+    range: &DateRange,
+    timezone: FixedOffset,
+    progress: UnboundedSender<f64>,
+) -> Result<DataFrame, String> {
+    // Clone the handle so other requests are not blocked during the export
+    let db = db.lock().await.clone();
+
+    info!("Start to export data");
+
+    let users: Collection<User> = db.collection("users");
+    let filter = if classes.is_empty() {
+        doc! {}
+    } else {
+        doc! {"group": {"$in": classes}}
+    };
+    let total = users.count_documents(filter, None).await;
+    if let Err(e) = total {
+        return Err(format!("Failed to count users: {}", e));
+    }
+    let total = total.unwrap().max(1);
+
+    let activities: Collection<Document> = db.collection("activities");
+    let cursor = activities
+        .aggregate(export_pipeline(classes, range, timezone), None)
+        .await;
+    if let Err(e) = cursor {
+        return Err(format!("Failed to aggregate documents: {}", e));
+    }
+    let mut cursor = cursor.unwrap();
+
+    let mut columns = ExportColumns::default();
+    let mut totals: BTreeMap<String, UserActivityTime> = BTreeMap::new();
+    let mut count = 0;
+    loop {
+        let document = cursor.try_next().await;
+        if let Err(e) = document {
+            return Err(format!("Failed to read documents: {}", e));
         }
-        let mut cursor = cursor.unwrap();
-        println!("Unwrapped cursor");
-        let result = cursor.try_next().await;
-        if let Err(_) = result {
-            return Err("Failed to get result".to_string());
+        let document = document.unwrap();
+        if document.is_none() {
+            break;
         }
-        println!("Unwrapped cursor");
-        let result = result.unwrap();
-        if let None = result {
-            continue;
+        let row: Result<ExportRow, _> = bson::from_document(document.unwrap());
+        if let Err(e) = row {
+            return Err(format!("Failed to parse document: {}", e));
         }
-        println!("Unwrapped cursor");
-        let result = result.unwrap();
-        println!("Got result {:?}", result);
-        let result: UserActivityTime = from_document(result).unwrap();
-        println!("Got result");
-        let extend = DataFrame::new(vec![
-            Series::new("_id", vec![doc._id.clone().to_hex()]),
-            Series::new("id", vec![doc.id.clone()]),
-            Series::new("name", vec![doc.name.clone()]),
-            Series::new("class", vec!["".to_string()]),
-            Series::new("on_campus", vec![result.on_campus]),
-            Series::new("off_campus", vec![result.off_campus]),
-            Series::new("social_practice", vec![result.social_practice]),
-            Series::new("total", vec![result.total]),
-        ]);
-        if let Err(_) = extend {
-            return Err("Failed to create DataFrame".to_string());
+        let row = row.unwrap();
+        if !classes.is_empty() {
+            totals.entry(row.class()).or_default().merge(&row.time());
         }
-        println!("Extended {}'s data", doc.name);
-        let extend = extend.unwrap();
-        df.extend(&extend).unwrap();
+        columns.push_row(&row);
+        count += 1;
+        let _ = progress.send(count as f64 / total as f64 * 100.0);
+    }
+    info!("Exported {} users", count);
+    for (class, time) in totals.iter() {
+        columns.push(
+            String::new(),
+            String::new(),
+            "Total".to_string(),
+            class.clone(),
+            time,
+        );
     }
-    Ok(df)
+    columns.into_dataframe()
 }
 
+/// Excel only detects UTF-8 in CSV files when they start with a byte order mark.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
 pub async fn save_to_csv(mut df: DataFrame, mut target: &File) -> Result<(), String> {
+    if target.write_all(UTF8_BOM).is_err() {
+        return Err("Failed to write DataFrame".to_string());
+    }
     let writer = CsvWriter::new(&mut target).finish(&mut df);
-    println!("Finished writing");
-    if let Err(_) = writer {
+    info!("Finished writing");
+    if writer.is_err() {
         return Err("Failed to write DataFrame".to_string());
     }
     Ok(())
@@ -0,0 +1,120 @@
+use polars::{frame::DataFrame, prelude::AnyValue};
+use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet};
+use std::{collections::BTreeMap, fs::File};
+use tracing::info;
+
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+
+/// Excel forbids some characters in sheet names and limits them to 31 characters.
+pub fn sheet_name(value: &str) -> String {
+    let name: String = value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            _ => c,
+        })
+        .take(MAX_SHEET_NAME_LENGTH)
+        .collect();
+    let name = name.trim_matches('\'').to_string();
+    if name.is_empty() {
+        "Unassigned".to_string()
+    } else {
+        name
+    }
+}
+
+/// Group the row indices of the frame by sheet name, keeping the sheets sorted.
+pub fn split_by_column(
+    df: &DataFrame,
+    column: &str,
+) -> Result<BTreeMap<String, Vec<usize>>, String> {
+    let values = df.column(column).map_err(|e| e.to_string())?;
+    let mut sheets: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for row in 0..df.height() {
+        let value = match values.get(row).map_err(|e| e.to_string())? {
+            AnyValue::String(value) => value.to_string(),
+            AnyValue::StringOwned(value) => value.to_string(),
+            AnyValue::Null => String::new(),
+            value => value.to_string(),
+        };
+        sheets.entry(sheet_name(&value)).or_default().push(row);
+    }
+    Ok(sheets)
+}
+
+fn write_sheet(
+    worksheet: &mut Worksheet,
+    df: &DataFrame,
+    rows: &[usize],
+    header: &Format,
+    number: &Format,
+) -> Result<(), String> {
+    for (col, name) in df.get_column_names().iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, col as u16, *name, header)
+            .map_err(|e| e.to_string())?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let target = index as u32 + 1;
+        for (col, series) in df.get_columns().iter().enumerate() {
+            let col = col as u16;
+            let value = series.get(*row).map_err(|e| e.to_string())?;
+            let result = match value {
+                AnyValue::Null => continue,
+                AnyValue::String(value) => worksheet.write_string(target, col, value),
+                AnyValue::StringOwned(ref value) => {
+                    worksheet.write_string(target, col, value.as_str())
+                }
+                AnyValue::Boolean(value) => worksheet.write_boolean(target, col, value),
+                AnyValue::Float64(value) => {
+                    worksheet.write_number_with_format(target, col, value, number)
+                }
+                AnyValue::Float32(value) => {
+                    worksheet.write_number_with_format(target, col, value, number)
+                }
+                AnyValue::Int64(value) => worksheet.write_number(target, col, value as f64),
+                AnyValue::Int32(value) => worksheet.write_number(target, col, value),
+                AnyValue::UInt64(value) => worksheet.write_number(target, col, value as f64),
+                AnyValue::UInt32(value) => worksheet.write_number(target, col, value),
+                value => worksheet.write_string(target, col, value.to_string()),
+            };
+            result.map_err(|e| e.to_string())?;
+        }
+    }
+    worksheet
+        .set_freeze_panes(1, 0)
+        .map_err(|e| e.to_string())?;
+    worksheet.autofit();
+    Ok(())
+}
+
+/// Write the frame as a workbook with one sheet per value of `sheets`,
+/// usually the class.
+pub async fn save_to_excel(df: DataFrame, sheets: &str, target: &File) -> Result<(), String> {
+    let header = Format::new()
+        .set_bold()
+        .set_font_color(Color::White)
+        .set_background_color(Color::RGB(0x4472C4))
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Center);
+    let number = Format::new().set_num_format("0.0");
+
+    let mut workbook = Workbook::new();
+    let sheets = split_by_column(&df, sheets)?;
+    if sheets.is_empty() {
+        let worksheet = workbook.add_worksheet();
+        write_sheet(worksheet, &df, &[], &header, &number)?;
+    }
+    for (name, rows) in sheets.iter() {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(name).map_err(|e| e.to_string())?;
+        write_sheet(worksheet, &df, rows, &header, &number)?;
+    }
+    let result = workbook.save_to_writer(target);
+    info!("Finished writing");
+    if let Err(e) = result {
+        return Err(format!("Failed to write workbook: {}", e));
+    }
+    Ok(())
+}
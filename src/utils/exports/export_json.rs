@@ -0,0 +1,46 @@
+use polars::{frame::DataFrame, prelude::AnyValue};
+use serde_json::{Map, Value};
+use std::{fs::File, io::Write};
+
+fn to_json_value(value: AnyValue) -> Value {
+    match value {
+        AnyValue::Null => Value::Null,
+        AnyValue::Boolean(value) => Value::from(value),
+        AnyValue::String(value) => Value::from(value),
+        AnyValue::StringOwned(value) => Value::from(value.as_str()),
+        AnyValue::Float64(value) => Value::from(value),
+        AnyValue::Float32(value) => Value::from(value),
+        AnyValue::Int64(value) => Value::from(value),
+        AnyValue::Int32(value) => Value::from(value),
+        AnyValue::UInt64(value) => Value::from(value),
+        AnyValue::UInt32(value) => Value::from(value),
+        value => Value::from(value.to_string()),
+    }
+}
+
+/// Convert the frame into an array of row objects keyed by column name.
+pub fn dataframe_to_json(df: &DataFrame) -> Result<Value, String> {
+    let names = df.get_column_names();
+    let mut rows = Vec::with_capacity(df.height());
+    for row in 0..df.height() {
+        let mut object = Map::new();
+        for (name, series) in names.iter().zip(df.get_columns()) {
+            let value = series.get(row).map_err(|e| e.to_string())?;
+            object.insert(name.to_string(), to_json_value(value));
+        }
+        rows.push(Value::Object(object));
+    }
+    Ok(Value::Array(rows))
+}
+
+pub async fn save_to_json(df: DataFrame, mut target: &File) -> Result<(), String> {
+    let json = dataframe_to_json(&df)?;
+    let result = serde_json::to_writer(&mut target, &json);
+    if result.is_err() {
+        return Err("Failed to write JSON".to_string());
+    }
+    if target.flush().is_err() {
+        return Err("Failed to write JSON".to_string());
+    }
+    Ok(())
+}
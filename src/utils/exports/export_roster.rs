@@ -0,0 +1,183 @@
+use crate::utils::dates::DateRange;
+use bson::{doc, oid::ObjectId, Bson, Document};
+use chrono::{DateTime, FixedOffset};
+use futures::stream::TryStreamExt;
+use mongodb::{Collection, Database};
+use polars::{frame::DataFrame, prelude::NamedFrom, series::Series};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// One activity member as produced by [`roster_pipeline`].
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RosterRow {
+    pub activity: String,
+    #[serde(default)]
+    pub date: Bson,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub classes: Vec<String>,
+    #[serde(default)]
+    pub mode: String,
+    #[serde(default)]
+    pub duration: Bson,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub impression: String,
+}
+
+/// Formats an activity date, stored as a UNIX timestamp or as a string, as
+/// `YYYY-MM-DD`.
+pub fn format_date(date: &Bson, timezone: FixedOffset) -> String {
+    let seconds = match date {
+        Bson::String(date) => return date.chars().take(10).collect(),
+        Bson::Int64(seconds) => *seconds,
+        Bson::Int32(seconds) => *seconds as i64,
+        Bson::Double(seconds) => *seconds as i64,
+        Bson::DateTime(date) => date.timestamp_millis() / 1000,
+        _ => return String::new(),
+    };
+    DateTime::from_timestamp(seconds, 0)
+        .map(|date| date.with_timezone(&timezone).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Lists the members of `activities`, or of every activity when empty,
+/// within `range`, with their number, name and class names.
+pub fn roster_pipeline(
+    activities: &[ObjectId],
+    range: &DateRange,
+    timezone: FixedOffset,
+) -> Vec<Document> {
+    let mut pipeline = vec![];
+    if !activities.is_empty() {
+        pipeline.push(doc! {"$match": {"_id": {"$in": activities}}});
+    }
+    pipeline.extend(range.stages(timezone));
+    pipeline.extend([
+        doc! {"$sort": {"date": 1, "_id": 1}},
+        doc! {"$unwind": "$members"},
+        doc! {"$lookup": {
+            "from": "users",
+            "localField": "members._id",
+            "foreignField": "_id",
+            "as": "user",
+        }},
+        doc! {"$unwind": {"path": "$user", "preserveNullAndEmptyArrays": true}},
+        doc! {"$lookup": {
+            "from": "groups",
+            "localField": "user.group",
+            "foreignField": "_id",
+            "as": "groups",
+        }},
+        doc! {"$project": {
+            "_id": 0,
+            "activity": "$name",
+            "date": "$date",
+            "id": "$user.id",
+            "name": "$user.name",
+            "classes": {"$map": {
+                "input": {"$filter": {
+                    "input": "$groups",
+                    "cond": {"$eq": ["$$this.type", "class"]},
+                }},
+                "in": "$$this.name",
+            }},
+            "mode": "$members.mode",
+            "duration": "$members.duration",
+            "status": "$members.status",
+            "impression": {"$ifNull": ["$members.impression", ""]},
+        }},
+    ]);
+    pipeline
+}
+
+/// Column buffers for the roster export.
+#[derive(Debug, Default)]
+pub struct RosterColumns {
+    activity: Vec<String>,
+    date: Vec<String>,
+    id: Vec<String>,
+    name: Vec<String>,
+    class: Vec<String>,
+    mode: Vec<String>,
+    duration: Vec<f64>,
+    status: Vec<String>,
+    impression: Vec<String>,
+}
+
+impl RosterColumns {
+    pub fn push(&mut self, row: RosterRow, timezone: FixedOffset) {
+        let duration = match row.duration {
+            Bson::Double(duration) => duration,
+            Bson::Int32(duration) => duration as f64,
+            Bson::Int64(duration) => duration as f64,
+            _ => 0.0,
+        };
+        self.date.push(format_date(&row.date, timezone));
+        self.activity.push(row.activity);
+        self.id.push(row.id);
+        self.name.push(row.name);
+        self.class.push(row.classes.join(", "));
+        self.mode.push(row.mode);
+        self.duration.push(duration);
+        self.status.push(row.status);
+        self.impression.push(row.impression);
+    }
+
+    pub fn into_dataframe(self) -> Result<DataFrame, String> {
+        let df = DataFrame::new(vec![
+            Series::new("activity", self.activity),
+            Series::new("date", self.date),
+            Series::new("id", self.id),
+            Series::new("name", self.name),
+            Series::new("class", self.class),
+            Series::new("mode", self.mode),
+            Series::new("duration", self.duration),
+            Series::new("status", self.status),
+            Series::new("impression", self.impression),
+        ]);
+        if df.is_err() {
+            return Err("Failed to create DataFrame".to_string());
+        }
+        Ok(df.unwrap())
+    }
+}
+
+pub async fn export_roster(
+    db: Arc<Mutex<Database>>,
+    activities: &[ObjectId],
+    range: &DateRange,
+    timezone: FixedOffset,
+) -> Result<DataFrame, String> {
+    let db = db.lock().await.clone();
+    let collection: Collection<Document> = db.collection("activities");
+    let cursor = collection
+        .aggregate(roster_pipeline(activities, range, timezone), None)
+        .await;
+    if let Err(e) = cursor {
+        return Err(format!("Failed to aggregate documents: {}", e));
+    }
+    let mut cursor = cursor.unwrap();
+    let mut columns = RosterColumns::default();
+    loop {
+        let document = cursor.try_next().await;
+        if let Err(e) = document {
+            return Err(format!("Failed to read documents: {}", e));
+        }
+        let document = document.unwrap();
+        if document.is_none() {
+            break;
+        }
+        let row: Result<RosterRow, _> = bson::from_document(document.unwrap());
+        if let Err(e) = row {
+            return Err(format!("Failed to parse document: {}", e));
+        }
+        columns.push(row.unwrap(), timezone);
+    }
+    columns.into_dataframe()
+}
@@ -1,2 +1,6 @@
-pub mod csv_to_excel;
 pub mod export_csv;
+pub mod export_excel;
+pub mod export_json;
+pub mod export_roster;
+pub mod schedules;
+pub mod tasks;
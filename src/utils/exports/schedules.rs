@@ -0,0 +1,267 @@
+use crate::{
+    models::{
+        exports::Task,
+        notifications::{Notification, NotificationType},
+        schedules::ExportSchedule,
+    },
+    utils::exports::tasks::delete_tasks,
+};
+use bson::{doc, oid::ObjectId};
+use chrono::{DateTime, FixedOffset};
+use cron::Schedule;
+use futures::stream::TryStreamExt;
+use mongodb::{options::FindOptions, Collection, Database};
+use std::str::FromStr;
+
+/// Schedules keep this many finished exports unless told otherwise.
+pub const DEFAULT_KEEP: u64 = 4;
+pub const MAX_KEEP: u64 = 52;
+
+fn collection(db: &Database) -> Collection<ExportSchedule> {
+    db.collection("schedules")
+}
+
+/// Weekday names by their number in standard cron, where Sunday is 0.
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Rewrites numeric weekdays as names. Standard cron counts from Sunday = 0
+/// (or 7), while the parser counts from Sunday = 1, so `1` would run on
+/// Sundays instead of Mondays.
+fn name_weekdays(field: &str) -> Result<String, String> {
+    let mut parts = vec![];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let days: Result<Vec<usize>, _> = range.split('-').map(str::parse::<usize>).collect();
+        if days.is_err() {
+            // Names, `*` and `?` mean the same to the parser
+            parts.push(part.to_string());
+            continue;
+        }
+        let days = days.unwrap();
+        if days.len() > 2 || days.iter().any(|day| *day > 7) {
+            return Err(format!("Invalid weekday: {}", part));
+        }
+        let named = match days[..] {
+            [day] => WEEKDAYS[day % 7].to_string(),
+            [0, 7] => "SUN-SAT".to_string(),
+            // The parser's week starts on Sunday, so a range through Sunday
+            // ends on Saturday and adds Sunday
+            [from, 7] if step.is_none() => format!("{}-SAT,SUN", WEEKDAYS[from % 7]),
+            [_, 7] => return Err(format!("Use weekday names in {}", part)),
+            [from, to] => format!("{}-{}", WEEKDAYS[from], WEEKDAYS[to]),
+            _ => unreachable!(),
+        };
+        match step {
+            Some(step) => parts.push(format!("{}/{}", named, step)),
+            None => parts.push(named),
+        }
+    }
+    Ok(parts.join(","))
+}
+
+/// Parses a standard five-field cron expression, or a shorthand such as
+/// `@weekly`. Weekdays count from Sunday = 0 as usual, or go by names like
+/// `MON`. Unlike standard cron, restricting both the day of the month and
+/// the day of the week matches only days that satisfy both, so
+/// `0 0 13 * FRI` runs on Friday the 13th only.
+pub fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let expression = expression.trim();
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let expression = if expression.starts_with('@') {
+        expression.to_string()
+    } else if fields.len() == 5 {
+        let weekdays = name_weekdays(fields[4])?;
+        // The parser also wants seconds
+        format!("0 {} {}", fields[..4].join(" "), weekdays)
+    } else {
+        return Err("Cron expressions have five fields".to_string());
+    };
+    let schedule = Schedule::from_str(&expression);
+    if let Err(e) = schedule {
+        return Err(format!("Invalid cron expression: {}", e));
+    }
+    Ok(schedule.unwrap())
+}
+
+/// The first time after `after` that `expression` matches, both as UNIX
+/// timestamps.
+pub fn next_run(expression: &str, after: u64, timezone: FixedOffset) -> Result<u64, String> {
+    let schedule = parse_cron(expression)?;
+    let after = DateTime::from_timestamp(after as i64, 0);
+    if after.is_none() {
+        return Err("Invalid timestamp".to_string());
+    }
+    let after = after.unwrap().with_timezone(&timezone);
+    let next = schedule.after(&after).next();
+    if next.is_none() {
+        return Err("Cron expression never matches".to_string());
+    }
+    Ok(next.unwrap().timestamp() as u64)
+}
+
+pub async fn insert_schedule(db: &Database, schedule: &ExportSchedule) -> Result<(), String> {
+    let result = collection(db).insert_one(schedule, None).await;
+    if let Err(e) = result {
+        return Err(format!("Failed to save schedule: {}", e));
+    }
+    Ok(())
+}
+
+/// Schedules of `owner`, or of everyone when not set, soonest first.
+pub async fn find_schedules(
+    db: &Database,
+    owner: Option<ObjectId>,
+) -> Result<Vec<ExportSchedule>, String> {
+    let filter = match owner {
+        Some(owner) => doc! {"owner": owner},
+        None => doc! {},
+    };
+    let options = FindOptions::builder().sort(doc! {"nextRun": 1}).build();
+    let schedules = collection(db).find(filter, options).await;
+    if let Err(e) = schedules {
+        return Err(format!("Failed to find schedules: {}", e));
+    }
+    let schedules = schedules.unwrap().try_collect().await;
+    if let Err(e) = schedules {
+        return Err(format!("Failed to find schedules: {}", e));
+    }
+    Ok(schedules.unwrap())
+}
+
+pub async fn find_schedule(
+    db: &Database,
+    schedule_id: ObjectId,
+) -> Result<Option<ExportSchedule>, String> {
+    let schedule = collection(db)
+        .find_one(doc! {"_id": schedule_id}, None)
+        .await;
+    if let Err(e) = schedule {
+        return Err(format!("Failed to find schedule: {}", e));
+    }
+    Ok(schedule.unwrap())
+}
+
+/// Deletes a schedule along with the exports it produced.
+pub async fn delete_schedule(db: &Database, schedule_id: ObjectId) -> Result<(), String> {
+    let result = collection(db)
+        .delete_one(doc! {"_id": schedule_id}, None)
+        .await;
+    if let Err(e) = result {
+        return Err(format!("Failed to delete schedule: {}", e));
+    }
+    delete_tasks(db, doc! {"schedule": schedule_id}).await?;
+    Ok(())
+}
+
+/// Schedules due at `now`, a UNIX timestamp.
+pub async fn find_due_schedules(db: &Database, now: u64) -> Result<Vec<ExportSchedule>, String> {
+    let schedules = collection(db)
+        .find(doc! {"nextRun": {"$lte": now as i64}}, None)
+        .await;
+    if let Err(e) = schedules {
+        return Err(format!("Failed to find due schedules: {}", e));
+    }
+    let schedules = schedules.unwrap().try_collect().await;
+    if let Err(e) = schedules {
+        return Err(format!("Failed to find due schedules: {}", e));
+    }
+    Ok(schedules.unwrap())
+}
+
+/// Moves a due schedule on to `next`. Returns false when the schedule was
+/// deleted or already moved on, in which case it must not run.
+pub async fn advance_schedule(
+    db: &Database,
+    schedule: &ExportSchedule,
+    next: u64,
+    now: u64,
+) -> Result<bool, String> {
+    let result = collection(db)
+        .update_one(
+            doc! {"_id": schedule._id, "nextRun": schedule.next_run as i64},
+            doc! {"$set": {"nextRun": next as i64, "lastRun": now as i64}},
+            None,
+        )
+        .await;
+    if let Err(e) = result {
+        return Err(format!("Failed to update schedule: {}", e));
+    }
+    Ok(result.unwrap().modified_count == 1)
+}
+
+/// Deletes all but the latest `keep` finished exports of a schedule.
+pub async fn prune_scheduled_tasks(
+    db: &Database,
+    schedule_id: ObjectId,
+    keep: u64,
+) -> Result<u64, String> {
+    let tasks: Collection<Task> = db.collection("exports");
+    let options = FindOptions::builder()
+        .sort(doc! {"time": -1})
+        .skip(keep)
+        .build();
+    let old = tasks
+        .find(
+            doc! {"schedule": schedule_id, "status": {"$in": ["done", "error"]}},
+            options,
+        )
+        .await;
+    if let Err(e) = old {
+        return Err(format!("Failed to find scheduled tasks: {}", e));
+    }
+    let old: Result<Vec<Task>, _> = old.unwrap().try_collect().await;
+    if let Err(e) = old {
+        return Err(format!("Failed to find scheduled tasks: {}", e));
+    }
+    let old: Vec<String> = old.unwrap().into_iter().map(|task| task._id).collect();
+    if old.is_empty() {
+        return Ok(0);
+    }
+    delete_tasks(db, doc! {"_id": {"$in": old}}).await
+}
+
+/// The notification telling the owner of a schedule how its run went.
+pub fn scheduled_task_notification(
+    schedule: &ExportSchedule,
+    task: &Task,
+    now: u64,
+) -> Notification {
+    let (title, content) = match &task.result {
+        Some(url) => (
+            "Scheduled export finished".to_string(),
+            format!("Download the {} export at {}", task.kind.file_name(), url),
+        ),
+        None => (
+            "Scheduled export failed".to_string(),
+            format!(
+                "The {} export could not be finished and will be tried again at the next run",
+                task.kind.file_name()
+            ),
+        ),
+    };
+    Notification {
+        _id: ObjectId::new(),
+        title,
+        content: Some(content),
+        time: now,
+        notification_type: NotificationType::Normal,
+        publisher: schedule.owner,
+        receivers: Some(vec![schedule.owner]),
+        anoymous: false,
+        global: false,
+        // The next run replaces this export
+        expire: schedule.next_run.max(now),
+    }
+}
+
+pub async fn notify_owner(db: &Database, notification: &Notification) -> Result<(), String> {
+    let notifications: Collection<Notification> = db.collection("notifications");
+    let result = notifications.insert_one(notification, None).await;
+    if let Err(e) = result {
+        return Err(format!("Failed to send notification: {}", e));
+    }
+    Ok(())
+}
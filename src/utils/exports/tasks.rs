@@ -0,0 +1,108 @@
+use crate::models::exports::{ExportFormat, Task};
+use bson::{doc, Document};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+    Collection, Database,
+};
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Where finished export files are kept until they expire.
+pub const EXPORT_DIRECTORY: &str = "public/exports";
+
+fn collection(db: &Database) -> Collection<Task> {
+    db.collection("exports")
+}
+
+pub fn export_file(task_id: &str, format: &ExportFormat) -> PathBuf {
+    PathBuf::from(EXPORT_DIRECTORY).join(format!("{}.{}", task_id, format.extension()))
+}
+
+pub fn download_url(task_id: &str) -> String {
+    format!("/export/{}/download", task_id)
+}
+
+pub async fn insert_task(db: &Database, task: &Task) -> Result<(), String> {
+    let result = collection(db).insert_one(task, None).await;
+    if let Err(e) = result {
+        return Err(format!("Failed to save task: {}", e));
+    }
+    Ok(())
+}
+
+pub async fn find_task(db: &Database, task_id: &str) -> Result<Option<Task>, String> {
+    let task = collection(db).find_one(doc! {"_id": task_id}, None).await;
+    if let Err(e) = task {
+        return Err(format!("Failed to find task: {}", e));
+    }
+    Ok(task.unwrap())
+}
+
+/// Applies `$set` with `update` and returns the task as it is afterwards.
+pub async fn update_task(
+    db: &Database,
+    task_id: &str,
+    update: Document,
+) -> Result<Option<Task>, String> {
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    let task = collection(db)
+        .find_one_and_update(doc! {"_id": task_id}, doc! {"$set": update}, options)
+        .await;
+    if let Err(e) = task {
+        return Err(format!("Failed to update task: {}", e));
+    }
+    Ok(task.unwrap())
+}
+
+/// Tasks still pending or processing when the server stopped will never
+/// finish, so they are marked as failed on startup.
+pub async fn fail_interrupted_tasks(db: &Database) -> Result<u64, String> {
+    let result = collection(db)
+        .update_many(
+            doc! {"status": {"$in": ["pending", "processing"]}},
+            doc! {"$set": {"status": "error", "result": null}},
+            None,
+        )
+        .await;
+    if let Err(e) = result {
+        return Err(format!("Failed to fail interrupted tasks: {}", e));
+    }
+    Ok(result.unwrap().modified_count)
+}
+
+/// Deletes the tasks matching `filter` together with their files.
+pub async fn delete_tasks(db: &Database, filter: Document) -> Result<u64, String> {
+    let tasks = collection(db).find(filter.clone(), None).await;
+    if let Err(e) = tasks {
+        return Err(format!("Failed to find tasks: {}", e));
+    }
+    let tasks: Result<Vec<Task>, _> = tasks.unwrap().try_collect().await;
+    if let Err(e) = tasks {
+        return Err(format!("Failed to find tasks: {}", e));
+    }
+    for task in tasks.unwrap() {
+        let file = export_file(&task._id, &task.options.format);
+        if let Err(e) = tokio::fs::remove_file(&file).await {
+            if e.kind() != ErrorKind::NotFound {
+                return Err(format!("Failed to delete {}: {}", file.display(), e));
+            }
+        }
+    }
+    let result = collection(db).delete_many(filter, None).await;
+    if let Err(e) = result {
+        return Err(format!("Failed to delete tasks: {}", e));
+    }
+    Ok(result.unwrap().deleted_count)
+}
+
+/// Deletes tasks started more than `retention` hours before `now`, a UNIX
+/// timestamp in milliseconds, together with their files. Scheduled tasks are
+/// kept by count instead, see [`prune_scheduled_tasks`].
+///
+/// [`prune_scheduled_tasks`]: super::schedules::prune_scheduled_tasks
+pub async fn cleanup_expired_tasks(db: &Database, retention: u64, now: u64) -> Result<u64, String> {
+    let before = now.saturating_sub(retention * 3600 * 1000);
+    delete_tasks(db, doc! {"time": {"$lt": before as i64}, "schedule": null}).await
+}
@@ -0,0 +1,48 @@
+use crate::models::{groups::Group, users::User};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+
+/// Finds the class groups a user belongs to.
+pub async fn find_user_classes(db: &Database, user: ObjectId) -> Result<Vec<Group>, String> {
+    let collection: Collection<User> = db.collection("users");
+    let group_collection: Collection<Group> = db.collection("groups");
+    let user = collection.find_one(doc! {"_id": user}, None).await;
+    if user.is_err() {
+        return Err("Failed to find user".to_string());
+    }
+    let user = user.unwrap();
+    if user.is_none() {
+        return Err("User not found".to_string());
+    }
+    let user = user.unwrap();
+    let groups = group_collection
+        .find(doc! {"_id": {"$in": user.group}, "type": "class"}, None)
+        .await;
+    if groups.is_err() {
+        return Err("Failed to find user groups".to_string());
+    }
+    let groups = groups.unwrap().try_collect().await;
+    if groups.is_err() {
+        return Err("Cannot parse groups".to_string());
+    }
+    Ok(groups.unwrap())
+}
+
+/// Checks whether `target` belongs to one of `user`'s classes.
+pub async fn share_class(db: &Database, user: ObjectId, target: ObjectId) -> Result<bool, String> {
+    let classes = find_user_classes(db, user).await?;
+    let collection: Collection<User> = db.collection("users");
+    let target = collection.find_one(doc! {"_id": target}, None).await;
+    if target.is_err() {
+        return Err("Failed to find target user".to_string());
+    }
+    let target = target.unwrap();
+    if target.is_none() {
+        return Err("Target user not found".to_string());
+    }
+    let target = target.unwrap();
+    Ok(classes
+        .iter()
+        .any(|class| target.group.contains(&class._id)))
+}
@@ -1 +1,3 @@
+pub mod classes;
+pub mod permissions;
 pub mod same_class;
@@ -0,0 +1,41 @@
+use crate::models::{
+    groups::{Group, GroupPermission},
+    users::User,
+};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+
+/// Finds the permissions a user currently has through their groups, as a
+/// token issued now would carry.
+pub async fn find_user_permissions(
+    db: &Database,
+    user: ObjectId,
+) -> Result<Vec<GroupPermission>, String> {
+    let collection: Collection<User> = db.collection("users");
+    let group_collection: Collection<Group> = db.collection("groups");
+    let user = collection.find_one(doc! {"_id": user}, None).await;
+    if user.is_err() {
+        return Err("Failed to find user".to_string());
+    }
+    let user = user.unwrap();
+    if user.is_none() {
+        return Err("User not found".to_string());
+    }
+    let user = user.unwrap();
+    let groups = group_collection
+        .find(doc! {"_id": {"$in": user.group}}, None)
+        .await;
+    if groups.is_err() {
+        return Err("Failed to find user groups".to_string());
+    }
+    let groups: Result<Vec<Group>, _> = groups.unwrap().try_collect().await;
+    if groups.is_err() {
+        return Err("Cannot parse groups".to_string());
+    }
+    Ok(groups
+        .unwrap()
+        .into_iter()
+        .flat_map(|group| group.permissions)
+        .collect())
+}
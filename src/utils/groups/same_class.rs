@@ -14,39 +14,39 @@ pub async fn validate_same_class(
     let collection = db.clone().collection("users");
     let group_collection = db.clone().collection("groups");
     let user = collection.find_one(doc! {"_id": user}, None).await;
-    if let Err(_) = user {
+    if user.is_err() {
         return Err("Base user not found".to_string());
     }
     let user = user.unwrap();
-    if let None = user {
+    if user.is_none() {
         return Err("Target user not found".to_string());
     }
     let user = bson::from_document(user.unwrap());
-    if let Err(_) = user {
+    if user.is_err() {
         return Err("Base user not found".to_string());
     }
     let user: User = user.unwrap();
     let target = collection.find_one(doc! {"_id": target}, None).await;
-    if let Err(_) = target {
+    if target.is_err() {
         return Err("Target user not found".to_string());
     }
     let target = target.unwrap();
-    if let None = target {
+    if target.is_none() {
         return Err("Target user not found".to_string());
     }
     let target = bson::from_document(target.unwrap());
-    if let Err(_) = target {
+    if target.is_err() {
         return Err("Target user not found".to_string());
     }
     let target: User = target.unwrap();
     let groups = group_collection
         .find(doc! {"_id": {"$in": user.group}, "type": "class"}, None)
         .await;
-    if let Err(_) = groups {
+    if groups.is_err() {
         return Err("Base user group not found".to_string());
     }
     let groups = groups.unwrap().try_collect().await;
-    if let Err(_) = groups {
+    if groups.is_err() {
         return Err("Cannot parse groups".to_string());
     }
     let groups: Vec<Group> = groups.unwrap();
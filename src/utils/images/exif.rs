@@ -0,0 +1,155 @@
+// Lossless metadata removal: segments and chunks carrying EXIF, XMP, IPTC or
+// text are dropped while the image data is copied byte for byte.
+
+const ORIENTATION_TAG: u16 = 0x0112;
+
+/// Strips metadata from a JPEG, keeping only the EXIF orientation so photos
+/// taken on phones are still displayed upright.
+pub fn strip_jpeg(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 4 || data[0..2] != [0xFF, 0xD8] {
+        return Err("Invalid JPEG".to_string());
+    }
+    let mut output = vec![0xFF, 0xD8];
+    let mut pos = 2;
+    loop {
+        if pos + 2 > data.len() || data[pos] != 0xFF {
+            return Err("Invalid JPEG marker".to_string());
+        }
+        let marker = data[pos + 1];
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Markers without a payload
+            0x01 | 0xD0..=0xD7 => {
+                output.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            // End of image
+            0xD9 => {
+                output.extend_from_slice(&data[pos..pos + 2]);
+                return Ok(output);
+            }
+            _ => {}
+        }
+        if pos + 4 > data.len() {
+            return Err("Truncated JPEG".to_string());
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > data.len() {
+            return Err("Truncated JPEG".to_string());
+        }
+        let payload = &data[pos + 4..end];
+        match marker {
+            // Start of scan: the rest is entropy-coded image data
+            0xDA => {
+                output.extend_from_slice(&data[pos..]);
+                return Ok(output);
+            }
+            // APP1 (EXIF, XMP): keep only the orientation
+            0xE1 => {
+                if let Some(tiff) = payload.strip_prefix(b"Exif\0\0") {
+                    if let Some(orientation) = read_orientation(tiff) {
+                        if orientation != 1 {
+                            output.extend(orientation_segment(orientation));
+                        }
+                    }
+                }
+            }
+            // APP13 (IPTC) and comments
+            0xED | 0xFE => {}
+            _ => output.extend_from_slice(&data[pos..end]),
+        }
+        pos = end;
+    }
+}
+
+/// Strips metadata chunks from a PNG.
+pub fn strip_png(data: &[u8]) -> Result<Vec<u8>, String> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    if data.len() < 8 || data[0..8] != SIGNATURE {
+        return Err("Invalid PNG".to_string());
+    }
+    let mut output = SIGNATURE.to_vec();
+    let mut pos = 8;
+    loop {
+        if pos + 8 > data.len() {
+            return Err("Truncated PNG".to_string());
+        }
+        let length =
+            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let chunk_type = &data[pos + 4..pos + 8];
+        // Length, type, data and CRC
+        let end = pos + 12 + length;
+        if end > data.len() {
+            return Err("Truncated PNG".to_string());
+        }
+        match chunk_type {
+            b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME" => {}
+            _ => output.extend_from_slice(&data[pos..end]),
+        }
+        if chunk_type == b"IEND" {
+            return Ok(output);
+        }
+        pos = end;
+    }
+}
+
+fn read_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(0..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |i: usize| -> Option<u16> {
+        let bytes = [*tiff.get(i)?, *tiff.get(i + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |i: usize| -> Option<u32> {
+        let bytes = [
+            *tiff.get(i)?,
+            *tiff.get(i + 1)?,
+            *tiff.get(i + 2)?,
+            *tiff.get(i + 3)?,
+        ];
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    let ifd = u32_at(4)? as usize;
+    let count = u16_at(ifd)? as usize;
+    for i in 0..count {
+        let entry = ifd + 2 + i * 12;
+        if u16_at(entry)? == ORIENTATION_TAG {
+            return u16_at(entry + 8);
+        }
+    }
+    None
+}
+
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut payload = b"Exif\0\0MM\0\x2a\0\0\0\x08".to_vec();
+    // One IFD entry: orientation, SHORT, count 1, left-justified value
+    payload.extend(1u16.to_be_bytes());
+    payload.extend(ORIENTATION_TAG.to_be_bytes());
+    payload.extend(3u16.to_be_bytes());
+    payload.extend(1u32.to_be_bytes());
+    payload.extend(orientation.to_be_bytes());
+    payload.extend([0, 0]);
+    // No next IFD
+    payload.extend(0u32.to_be_bytes());
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend(((payload.len() + 2) as u16).to_be_bytes());
+    segment.extend(payload);
+    segment
+}
@@ -0,0 +1,48 @@
+pub mod exif;
+pub mod thumbnail;
+
+use magic::{cookie::Flags, Cookie};
+
+pub const ALLOWED_IMAGE_TYPES: [&str; 2] = ["image/jpeg", "image/png"];
+
+/// Detects the MIME type of `data` from its content with libmagic.
+pub fn detect_mime(data: &[u8]) -> Result<String, String> {
+    let cookie = Cookie::open(Flags::MIME_TYPE);
+    if let Err(e) = cookie {
+        return Err(format!("Failed to open libmagic: {}", e));
+    }
+    let cookie = cookie.unwrap().load(&Default::default());
+    if let Err(e) = cookie {
+        return Err(format!("Failed to load libmagic database: {}", e));
+    }
+    let mime = cookie.unwrap().buffer(data);
+    if let Err(e) = mime {
+        return Err(format!("Failed to detect file type: {}", e));
+    }
+    Ok(mime.unwrap())
+}
+
+pub fn image_extension(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        _ => "jpg",
+    }
+}
+
+/// Keys of the thumbnail and preview generated for the image stored at `key`.
+pub fn rendition_keys(key: &str) -> (String, String) {
+    let base = key.rsplit_once('.').map_or(key, |(base, _)| base);
+    (
+        format!("{}.thumb.webp", base),
+        format!("{}.preview.webp", base),
+    )
+}
+
+/// Removes EXIF and other embedded metadata from an image of an allowed type.
+pub fn strip_metadata(mime: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    match mime {
+        "image/jpeg" => exif::strip_jpeg(data),
+        "image/png" => exif::strip_png(data),
+        _ => Err(format!("Unsupported image type: {}", mime)),
+    }
+}
@@ -0,0 +1,73 @@
+use image::{
+    imageops::FilterType, DynamicImage, GenericImageView, ImageDecoder, ImageReader, Limits,
+};
+use std::io::Cursor;
+
+/// Downscaled copies of an uploaded image, encoded as WebP.
+pub struct Renditions {
+    pub thumbnail: Vec<u8>,
+    pub preview: Vec<u8>,
+}
+
+// Larger images are refused rather than decoded, to bound memory use.
+const MAX_DIMENSION: u32 = 12000;
+
+pub fn create_renditions(
+    data: &[u8],
+    thumbnail_size: u32,
+    preview_size: u32,
+) -> Result<Renditions, String> {
+    let reader = ImageReader::new(Cursor::new(data)).with_guessed_format();
+    if reader.is_err() {
+        return Err("Failed to read image".to_string());
+    }
+    let mut reader = reader.unwrap();
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+    let decoder = reader.into_decoder();
+    if let Err(e) = decoder {
+        return Err(format!("Failed to decode image: {}", e));
+    }
+    let mut decoder = decoder.unwrap();
+    // Renditions carry no metadata, so the orientation is applied to the pixels
+    let orientation = decoder.orientation();
+    if let Err(e) = orientation {
+        return Err(format!("Failed to decode image: {}", e));
+    }
+    let orientation = orientation.unwrap();
+    let image = DynamicImage::from_decoder(decoder);
+    if let Err(e) = image {
+        return Err(format!("Failed to decode image: {}", e));
+    }
+    let mut image = image.unwrap();
+    image.apply_orientation(orientation);
+    Ok(Renditions {
+        thumbnail: encode_webp(&downscale(&image, thumbnail_size), 70.0)?,
+        preview: encode_webp(&downscale(&image, preview_size), 80.0)?,
+    })
+}
+
+/// Fits the image within a `size` square, never upscaling.
+fn downscale(image: &DynamicImage, size: u32) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    if width <= size && height <= size {
+        return image.clone();
+    }
+    image.resize(size, size, FilterType::Triangle)
+}
+
+fn encode_webp(image: &DynamicImage, quality: f32) -> Result<Vec<u8>, String> {
+    // libwebp only takes 8-bit RGB or RGBA
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+    let encoder = webp::Encoder::from_image(&image);
+    if let Err(e) = encoder {
+        return Err(format!("Failed to encode WebP: {}", e));
+    }
+    Ok(encoder.unwrap().encode(quality).to_vec())
+}
@@ -0,0 +1,211 @@
+use crate::utils::config::{CheckMode, ImpressionConfig};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+
+const SHINGLE_SIZE: usize = 3;
+
+/// Something an auditor would object to in an impression.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImpressionIssue {
+    TooShort,
+    DuplicateInActivity,
+    DuplicateInHistory,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2A6DF}')
+}
+
+/// Length of an impression as a reader would judge it: every CJK character
+/// counts as one, as does every word written in other scripts. Whitespace
+/// and punctuation do not count.
+pub fn impression_length(text: &str) -> usize {
+    let mut length = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            length += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                length += 1;
+            }
+            in_word = true;
+        } else {
+            in_word = false;
+        }
+    }
+    length
+}
+
+fn hash_shingle(shingle: &[char]) -> u64 {
+    // FNV-1a followed by a SplitMix64 finalizer so every bit is well mixed
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for c in shingle {
+        for byte in (*c as u32).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58476d1ce4e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// Simhash over the character shingles of the text, ignoring case,
+/// whitespace and punctuation.
+pub fn simhash(text: &str) -> u64 {
+    let chars: Vec<char> = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    if chars.is_empty() {
+        return 0;
+    }
+    let mut weights = [0i64; 64];
+    for shingle in chars.windows(SHINGLE_SIZE.min(chars.len())) {
+        let hash = hash_shingle(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Similarity of two texts from 0 (unrelated) to 1 (same content).
+pub fn similarity(a: &str, b: &str) -> f64 {
+    1.0 - (simhash(a) ^ simhash(b)).count_ones() as f64 / 64.0
+}
+
+impl ImpressionIssue {
+    pub fn mode(&self, config: &ImpressionConfig) -> CheckMode {
+        match self {
+            ImpressionIssue::TooShort => config.length_check,
+            ImpressionIssue::DuplicateInActivity | ImpressionIssue::DuplicateInHistory => {
+                config.duplicate_check
+            }
+        }
+    }
+
+    pub fn describe(&self, config: &ImpressionConfig) -> String {
+        match self {
+            ImpressionIssue::TooShort => format!(
+                "Impression must be at least {} characters long",
+                config.min_length
+            ),
+            ImpressionIssue::DuplicateInActivity => {
+                "Impression is too similar to another member's".to_string()
+            }
+            ImpressionIssue::DuplicateInHistory => {
+                "Impression is too similar to one of your earlier impressions".to_string()
+            }
+        }
+    }
+}
+
+/// Checks an impression against the enabled rules. `activity` holds the other
+/// members' impressions for the same activity, `history` the member's own
+/// impressions elsewhere.
+pub fn check_impression(
+    config: &ImpressionConfig,
+    impression: &str,
+    activity: &[String],
+    history: &[String],
+) -> Vec<ImpressionIssue> {
+    let mut issues = vec![];
+    if config.length_check != CheckMode::Off && impression_length(impression) < config.min_length {
+        issues.push(ImpressionIssue::TooShort);
+    }
+    if config.duplicate_check != CheckMode::Off {
+        let duplicated = |others: &[String]| {
+            others
+                .iter()
+                .any(|other| similarity(impression, other) >= config.max_similarity)
+        };
+        if duplicated(activity) {
+            issues.push(ImpressionIssue::DuplicateInActivity);
+        }
+        if duplicated(history) {
+            issues.push(ImpressionIssue::DuplicateInHistory);
+        }
+    }
+    issues
+}
+
+/// The issues the configuration rejects, described for an error message, or
+/// none if the impression may be saved.
+pub fn rejection_message(config: &ImpressionConfig, issues: &[ImpressionIssue]) -> Option<String> {
+    let rejected: Vec<String> = issues
+        .iter()
+        .filter(|issue| issue.mode(config) == CheckMode::Reject)
+        .map(|issue| issue.describe(config))
+        .collect();
+    if rejected.is_empty() {
+        return None;
+    }
+    Some(rejected.join("; "))
+}
+
+/// Impressions to compare a member's impression against: those of the other
+/// members of the activity, and the member's own in other activities.
+pub async fn find_related_impressions(
+    db: &Database,
+    activity_id: ObjectId,
+    member_id: ObjectId,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let collection: Collection<bson::Document> = db.collection("activities");
+    let pipeline = vec![
+        doc! {"$match": {"$or": [{"_id": activity_id}, {"members._id": member_id}]}},
+        doc! {"$unwind": "$members"},
+        doc! {"$match": {
+            "members.impression": {"$nin": [null, ""]},
+            "$or": [
+                {"_id": activity_id, "members._id": {"$ne": member_id}},
+                {"_id": {"$ne": activity_id}, "members._id": member_id},
+            ],
+        }},
+        doc! {"$project": {"_id": 1, "impression": "$members.impression"}},
+    ];
+    let cursor = collection.aggregate(pipeline, None).await;
+    if cursor.is_err() {
+        return Err("Failed to find impressions".to_string());
+    }
+    let documents: Result<Vec<bson::Document>, _> = cursor.unwrap().try_collect().await;
+    if documents.is_err() {
+        return Err("Failed to find impressions".to_string());
+    }
+    let mut activity = vec![];
+    let mut history = vec![];
+    for document in documents.unwrap() {
+        let impression = document
+            .get_str("impression")
+            .unwrap_or_default()
+            .to_string();
+        if document.get_object_id("_id") == Ok(activity_id) {
+            activity.push(impression);
+        } else {
+            history.push(impression);
+        }
+    }
+    Ok((activity, history))
+}
@@ -14,6 +14,7 @@ use axum_extra::{
 };
 use serde::{Deserialize, Serialize};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum AuthenticationError {
     InvalidToken,
@@ -1,7 +1,15 @@
 pub mod aes;
+pub mod attendances;
+pub mod certificates;
 pub mod config;
-// pub mod exports;
+pub mod cursor;
+pub mod dates;
+pub mod exports;
 pub mod groups;
+pub mod images;
+pub mod impressions;
 pub mod jwt;
+pub mod requirements;
 pub mod rsa;
+pub mod storage;
 pub mod users;
@@ -0,0 +1,94 @@
+use crate::{
+    models::groups::Group, routers::users::time::UserActivityTime,
+    utils::config::RequirementProfile,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CategoryProgress {
+    pub required: f64,
+    pub completed: f64,
+    pub shortfall: f64,
+}
+
+impl CategoryProgress {
+    fn new(required: f64, completed: f64) -> Self {
+        CategoryProgress {
+            required,
+            completed,
+            shortfall: (required - completed).max(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RequirementProgress {
+    /// Name of the applied profile, if any applies.
+    pub profile: Option<String>,
+    pub on_campus: CategoryProgress,
+    pub off_campus: CategoryProgress,
+    pub social_practice: CategoryProgress,
+    pub total: CategoryProgress,
+    /// Whether every requirement is met.
+    pub eligible: bool,
+}
+
+fn profile_applies(profile: &RequirementProfile, classes: &[Group]) -> bool {
+    let in_classes = profile.classes.as_ref().is_none_or(|ids| {
+        classes
+            .iter()
+            .any(|class| ids.contains(&class._id.to_hex()))
+    });
+    let in_grade = profile.grade.as_ref().is_none_or(|grade| {
+        classes
+            .iter()
+            .any(|class| class.name.starts_with(grade.as_str()))
+    });
+    in_classes && in_grade
+}
+
+/// The most specific profile for a student in `classes`: naming classes wins
+/// over naming a grade, which wins over naming neither.
+pub fn find_requirement_profile<'a>(
+    profiles: &'a [RequirementProfile],
+    classes: &[Group],
+) -> Option<&'a RequirementProfile> {
+    profiles
+        .iter()
+        .rev()
+        .filter(|profile| profile_applies(profile, classes))
+        .max_by_key(|profile| (profile.classes.is_some(), profile.grade.is_some()))
+}
+
+pub fn calculate_progress(
+    profile: Option<&RequirementProfile>,
+    time: &UserActivityTime,
+) -> RequirementProgress {
+    let default = RequirementProfile {
+        on_campus: 0.0,
+        off_campus: 0.0,
+        social_practice: 0.0,
+        total: 0.0,
+        ..Default::default()
+    };
+    let required = profile.unwrap_or(&default);
+    let progress = RequirementProgress {
+        profile: profile.map(|profile| profile.name.clone()),
+        on_campus: CategoryProgress::new(required.on_campus, time.on_campus),
+        off_campus: CategoryProgress::new(required.off_campus, time.off_campus),
+        social_practice: CategoryProgress::new(required.social_practice, time.social_practice),
+        total: CategoryProgress::new(required.total, time.total),
+        eligible: false,
+    };
+    RequirementProgress {
+        eligible: [
+            &progress.on_campus,
+            &progress.off_campus,
+            &progress.social_practice,
+            &progress.total,
+        ]
+        .iter()
+        .all(|category| category.shortfall == 0.0),
+        ..progress
+    }
+}
@@ -6,6 +6,7 @@ use rsa::{
 };
 use tokio::fs::{write, File};
 use tokio::io::AsyncReadExt;
+use tracing::info;
 
 pub async fn generate_keypair() -> (RsaPrivateKey, RsaPublicKey) {
     let mut rng = OsRng;
@@ -41,7 +42,7 @@ pub async fn load_keypair() -> (RsaPrivateKey, RsaPublicKey) {
     let private_key_pem = parse(&private_key_pem).unwrap();
 
     // Decode the private key from PKCS#1 DER
-    let private_key = RsaPrivateKey::from_pkcs1_der(&private_key_pem.contents()).unwrap();
+    let private_key = RsaPrivateKey::from_pkcs1_der(private_key_pem.contents()).unwrap();
 
     // Asynchronously read the public key PEM file
     let mut public_key_file = File::open("public.pem").await.unwrap();
@@ -53,9 +54,9 @@ pub async fn load_keypair() -> (RsaPrivateKey, RsaPublicKey) {
     let public_key_pem = parse(&public_key_pem).unwrap();
 
     // Decode the public key from PKCS#1 DER
-    let public_key = RsaPublicKey::from_pkcs1_der(&public_key_pem.contents()).unwrap();
+    let public_key = RsaPublicKey::from_pkcs1_der(public_key_pem.contents()).unwrap();
 
-    println!("RSA private key and public key loaded successfully.");
+    info!("RSA private key and public key loaded successfully.");
 
     (private_key, public_key)
 }
@@ -64,22 +65,14 @@ pub async fn load_keypair() -> (RsaPrivateKey, RsaPublicKey) {
 pub fn encrypt(public_key: &RsaPublicKey, message: &str) -> Vec<u8> {
     let mut rng = OsRng;
     let result = public_key.encrypt(&mut rng, pkcs1v15::Pkcs1v15Encrypt, message.as_bytes());
-    if let Ok(encrypted) = result {
-        encrypted
-    } else {
-        Vec::new()
-    }
+    result.unwrap_or_default()
 }
 
 pub async fn decrypt(private_key: &RsaPrivateKey, encrypted: &[u8]) -> String {
     let result = private_key.decrypt(pkcs1v15::Pkcs1v15Encrypt, encrypted);
     if let Ok(decrypted) = result {
         let result = String::from_utf8(decrypted);
-        if let Ok(decrypted) = result {
-            decrypted
-        } else {
-            String::new()
-        }
+        result.unwrap_or_default()
     } else {
         String::new()
     }
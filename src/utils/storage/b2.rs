@@ -0,0 +1,130 @@
+use crate::utils::storage::{Storage, StorageError};
+use axum::async_trait;
+use b2_client::{
+    account::Authorization, authorize_account, client::SurfClient, download_file, error::ErrorCode,
+    get_upload_authorization_by_id, hide_file_by_name, upload_file, DownloadFile, Error,
+    UploadFile,
+};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Tokens are valid for 24 hours, renew them a little earlier.
+const AUTHORIZATION_TTL: Duration = Duration::from_secs(23 * 3600);
+
+/// Stores objects in a Backblaze B2 bucket.
+pub struct B2Storage {
+    key_id: String,
+    key: String,
+    bucket_id: String,
+    bucket_name: String,
+    /// The account authorization and when it was obtained, shared by all
+    /// requests instead of authorizing each one.
+    auth: Mutex<Option<(Authorization<SurfClient>, Instant)>>,
+}
+
+impl B2Storage {
+    pub fn new(key_id: String, key: String, bucket_id: String, bucket_name: String) -> Self {
+        B2Storage {
+            key_id,
+            key,
+            bucket_id,
+            bucket_name,
+            auth: Mutex::new(None),
+        }
+    }
+
+    /// Locks the cached authorization, authorizing again once it expires.
+    async fn authorize(
+        &self,
+    ) -> Result<MutexGuard<'_, Option<(Authorization<SurfClient>, Instant)>>, String> {
+        let mut auth = self.auth.lock().await;
+        let expired = match auth.as_ref() {
+            Some((_, authorized)) => authorized.elapsed() >= AUTHORIZATION_TTL,
+            None => true,
+        };
+        if expired {
+            let renewed = authorize_account(SurfClient::default(), &self.key_id, &self.key).await;
+            if let Err(e) = renewed {
+                return Err(format!("Failed to authorize B2 account: {}", e));
+            }
+            *auth = Some((renewed.unwrap(), Instant::now()));
+        }
+        Ok(auth)
+    }
+
+    /// Drops the cached authorization if B2 no longer accepts it.
+    fn forget_rejected<E>(auth: &mut Option<(Authorization<SurfClient>, Instant)>, error: &Error<E>)
+    where
+        E: std::fmt::Debug + std::fmt::Display,
+    {
+        if let Error::B2(e) = error {
+            if matches!(
+                e.code(),
+                ErrorCode::BadAuthToken | ErrorCode::ExpiredAuthToken
+            ) {
+                *auth = None;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for B2Storage {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<(), String> {
+        let mut guard = self.authorize().await?;
+        let (auth, _) = guard.as_mut().unwrap();
+        let upload = get_upload_authorization_by_id(auth, &self.bucket_id).await;
+        if let Err(e) = upload {
+            let message = format!("Failed to authorize B2 upload: {}", e);
+            Self::forget_rejected(&mut guard, &e);
+            return Err(message);
+        }
+        let mut upload = upload.unwrap();
+        let file = UploadFile::builder().file_name(key);
+        if let Err(e) = file {
+            return Err(format!("Invalid object key: {}", e));
+        }
+        let file = file.unwrap().content_type(content_type).build();
+        if let Err(e) = file {
+            return Err(format!("Invalid upload: {}", e));
+        }
+        if let Err(e) = upload_file(&mut upload, file.unwrap(), &data).await {
+            return Err(format!("Failed to upload to B2: {}", e));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let mut guard = self.authorize().await.map_err(StorageError::Failed)?;
+        let (auth, _) = guard.as_mut().unwrap();
+        let file = DownloadFile::with_name(key, &self.bucket_name);
+        let result = download_file(auth, file).await;
+        if let Err(e) = result {
+            if let Error::B2(b2) = &e {
+                if matches!(
+                    b2.code(),
+                    ErrorCode::NoSuchFile | ErrorCode::FileNotPresent | ErrorCode::NotFound
+                ) {
+                    return Err(StorageError::NotFound);
+                }
+            }
+            let message = format!("Failed to download from B2: {}", e);
+            Self::forget_rejected(&mut guard, &e);
+            return Err(StorageError::Failed(message));
+        }
+        let (data, _) = result.unwrap();
+        Ok(data)
+    }
+
+    /// Hides the object, a lifecycle rule on the bucket removes hidden files.
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        let mut guard = self.authorize().await?;
+        let (auth, _) = guard.as_mut().unwrap();
+        if let Err(e) = hide_file_by_name(auth, &self.bucket_id, key).await {
+            let message = format!("Failed to delete from B2: {}", e);
+            Self::forget_rejected(&mut guard, &e);
+            return Err(message);
+        }
+        Ok(())
+    }
+}
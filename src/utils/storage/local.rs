@@ -0,0 +1,66 @@
+use crate::utils::storage::{Storage, StorageError};
+use axum::async_trait;
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+use tokio::fs::{create_dir_all, read, remove_file, write};
+
+/// Stores objects as files under a directory, for development and tests.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, _: &str, data: Vec<u8>) -> Result<(), String> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            if let Err(e) = create_dir_all(parent).await {
+                return Err(format!("Failed to create directory: {}", e));
+            }
+        }
+        if let Err(e) = write(path, data).await {
+            return Err(format!("Failed to write file: {}", e));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        // No object can live outside the root
+        if !is_relative_key(key) {
+            return Err(StorageError::NotFound);
+        }
+        let data = read(self.root.join(key)).await;
+        if let Err(e) = data {
+            if e.kind() == ErrorKind::NotFound {
+                return Err(StorageError::NotFound);
+            }
+            return Err(StorageError::Failed(format!("Failed to read file: {}", e)));
+        }
+        Ok(data.unwrap())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        if !is_relative_key(key) {
+            return Err("Invalid object key".to_string());
+        }
+        if let Err(e) = remove_file(self.root.join(key)).await {
+            return Err(format!("Failed to delete file: {}", e));
+        }
+        Ok(())
+    }
+}
+
+/// Keys are only ever relative paths below the root.
+fn is_relative_key(key: &str) -> bool {
+    Path::new(key)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
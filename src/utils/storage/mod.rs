@@ -0,0 +1,43 @@
+pub mod b2;
+pub mod local;
+pub mod signature;
+
+use crate::utils::config::{Config, StorageConfig};
+use axum::async_trait;
+use std::{fmt, sync::Arc};
+
+/// Why a stored object could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    NotFound,
+    Failed(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "Object not found"),
+            StorageError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// A place uploaded files are kept, addressed by object key.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> Result<(), String>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+    async fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+pub fn create_storage(config: &Config) -> Arc<dyn Storage> {
+    match config.storage.clone().unwrap_or_default() {
+        StorageConfig::Local { path } => Arc::new(local::LocalStorage::new(path)),
+        StorageConfig::B2 {
+            key_id,
+            key,
+            bucket_id,
+            bucket_name,
+        } => Arc::new(b2::B2Storage::new(key_id, key, bucket_id, bucket_name)),
+    }
+}
@@ -0,0 +1,51 @@
+use crate::utils::aes::generate_aes256_key;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Lifetime of a signed object URL, in seconds.
+pub const SIGNED_URL_TTL: i64 = 300;
+
+/// Secret for signing object URLs, kept apart from the data encryption key.
+pub const SIGNING_KEY_FILE: &str = "signing.key";
+
+pub fn read_signing_key() -> String {
+    let key = std::fs::read_to_string(SIGNING_KEY_FILE);
+    if key.is_err() {
+        let key = generate_aes256_key();
+        let _ = std::fs::write(SIGNING_KEY_FILE, key.as_bytes());
+        return key;
+    }
+    key.unwrap()
+}
+
+/// Signs an object key together with its expiry timestamp.
+pub fn sign_object_key(secret: &[u8], key: &str, expires: i64) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}\n{}", key, expires).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks a signature made by `sign_object_key` and that it has not expired at `now`.
+pub fn verify_object_key(
+    secret: &[u8],
+    key: &str,
+    expires: i64,
+    signature: &str,
+    now: i64,
+) -> Result<(), String> {
+    if expires < now {
+        return Err("Signed URL expired".to_string());
+    }
+    let signature = hex::decode(signature);
+    if signature.is_err() {
+        return Err("Invalid signature".to_string());
+    }
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}\n{}", key, expires).as_bytes());
+    if mac.verify_slice(&signature.unwrap()).is_err() {
+        return Err("Invalid signature".to_string());
+    }
+    Ok(())
+}